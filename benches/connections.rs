@@ -0,0 +1,48 @@
+use std::time::{Duration, Instant};
+use unet::client::ClientState;
+use unet::config::test::{connect_clients, multi_client_test_config};
+use unet::server::UnetServer;
+
+const MEASURED_TICKS: u32 = 200;
+
+fn server_tick(connections: usize) -> Duration {
+    let (mut server_config, client_configs) = multi_client_test_config(connections);
+    server_config.max_connections = connections;
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut clients = connect_clients(client_configs);
+
+    while clients
+        .iter()
+        .any(|client| client.state != ClientState::Connected)
+    {
+        for client in clients.iter_mut() {
+            client.tick();
+        }
+        server.tick();
+    }
+
+    let mut total = Duration::ZERO;
+    for _ in 0..MEASURED_TICKS {
+        for client in clients.iter_mut() {
+            client.tick();
+        }
+
+        let start = Instant::now();
+        server.tick();
+        total += start.elapsed();
+    }
+
+    total / MEASURED_TICKS
+}
+
+fn main() {
+    let mut results = vec![];
+    for connections in [1, 16, 256, 1024, 4096] {
+        results.push((connections, server_tick(connections)));
+    }
+
+    println!();
+    for (connections, per_tick) in results {
+        println!("{connections:>6} connections: {per_tick:>12?} per server tick");
+    }
+}
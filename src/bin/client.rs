@@ -1,8 +1,6 @@
 use console::Term;
 use std::sync::mpsc::channel;
 use std::thread;
-use std::thread::sleep;
-use std::time::Duration;
 use unet::client::UnetClient;
 use unet::config::client::ClientConfig;
 use unet::packet::data::Data;
@@ -11,7 +9,7 @@ use unet::packet::Packet;
 fn main() {
     let (rx, tx) = channel();
     let j1 = thread::spawn(move || {
-        let mut config = ClientConfig::default();
+        let mut config = ClientConfig::new();
         config.recv_debug = true;
         config.send_debug = true;
 
@@ -1,13 +1,21 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use unet::config::server::ServerConfig;
 use unet::server::UnetServer;
 
 fn main() {
     let mut config = ServerConfig::new();
-    // config.max_rolling_packets_per_tick = None;
+    // config.spam_policy = None;
     config.recv_debug = true;
     config.send_debug = true;
     let mut server = UnetServer::from_config(config).unwrap();
-    loop {
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler_running = running.clone();
+    ctrlc::set_handler(move || handler_running.store(false, Ordering::SeqCst)).unwrap();
+
+    while running.load(Ordering::SeqCst) {
         server.update();
     }
+    server.shutdown();
 }
@@ -3,7 +3,7 @@ use std::time::Duration;
 use tokio::time::sleep;
 use unet::client::UnetClient;
 use unet::packet::data::Data;
-use unet::packet::{Packet, UnetId};
+use unet::packet::Packet;
 use unet::MAX_CONNECTIONS;
 
 #[tokio::main(flavor = "multi_thread")]
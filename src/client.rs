@@ -1,19 +1,33 @@
+pub mod event;
+pub mod reconnect;
+
+use crate::client::event::ClientEvent;
+use crate::clock::{Clock, SystemClock};
 use crate::config::client::ClientConfig;
+use crate::congestion::{CongestionController, CongestionMode};
+use crate::cookie::Cookie;
 use crate::debug::{recv_dbg, send_dbg, BLUE};
-use crate::network::Network::{Real, Virtual};
-use crate::network::{Network, VirtualNetwork};
+use crate::mtu::MtuDiscovery;
+use crate::network::udp::{canonical_addr, UdpTransport};
+use crate::network::Transport;
 use crate::packet::challenge_response::ChallengeResponse;
 use crate::packet::connection_request::ConnectionRequest;
 use crate::packet::disconnect::{Disconnect, DisconnectReason};
 use crate::packet::keep_alive::KeepAlive;
+use crate::packet::mtu_probe::MtuProbe;
+use crate::packet::mtu_probe_ack::MtuProbeAck;
+use crate::packet::path_response::PathResponse;
+use crate::packet::ping::Ping;
+use crate::packet::pong::Pong;
 use crate::packet::{Packet, PacketKind, UnetId};
+use crate::replay_window::ReplayWindow;
+use crate::stats::{NetworkStats, StatsTracker};
 use crate::tick::Tick;
-use crate::{BUF_SIZE, DEFAULT_KEEP_ALIVE_FREQUENCY, DEFAULT_SERVER_NOT_RESPONDING_TIMEOUT};
+use crate::{BUF_SIZE, DEFAULT_KEEP_ALIVE_FREQUENCY, MAX_PACKET_SIZE};
 use colored::Colorize;
 use std::collections::VecDeque;
 use std::io;
-use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
-use std::thread::sleep;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::time::{Duration, Instant};
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -27,24 +41,40 @@ pub enum ClientState {
     Disconnected(DisconnectReason),
     SendingConnectionRequest,
     SendingConnectionResponse,
+    Queued, // Waiting for a slot on a full server
     Connected,
 }
 
 #[derive(Debug)]
 pub struct UnetClient {
-    pub id: UnetId,
+    pub id: UnetId, // Our own pick, the server knows us by the session id it assigns
+    session_id: Option<UnetId>, // Goes in every header once we're connected
+    stale_session_id: Option<UnetId>, // Ours before we reconnected, the server may still have it
     target: SocketAddr,
-    network: Network,
+    transport: Box<dyn Transport>,
     pub state: ClientState,
     pub send_queue: VecDeque<Packet>,
     pub config: ClientConfig,
     pub ticks_since_last_packet_sent: Tick, // Needed for tracking when to send KeepAlive
     pub ticks_since_last_packet_received: Tick, // Needed for timing out if server isn't responding
+    ticks_since_connect_started: Tick,      // Needed for the connect timeout
+    handshake_packet_due: bool,             // Send the next handshake packet without waiting
     pub sequence: u64,                      // Packet sequence
-    previous: Instant,                      // For update() loop
-    lag: u128,                              // For update() loop
-    terminate: bool,                        // For gracefully exiting
-    pub action_trace: Vec<Action>,          // Optional trace for Debugging
+    replay_window: ReplayWindow,            // Sequences received from the server
+    secret: u64,                            // Lets the server recognize us if our address changes
+    cookie: Cookie,                         // From the server's challenge, sent back to it
+    queue_position: Option<u32>,            // Our place in line while the server is full
+    stats: StatsTracker,                    // RTT, loss and bandwidth of the connection
+    congestion: Option<CongestionController>, // Throttles send_queue when the connection is bad
+    reconnect_attempt: u32,                 // Attempts since we were last connected
+    reconnect_in: Option<Tick>,             // Waiting this long before the next attempt
+    events: VecDeque<ClientEvent>,
+    mtu_discovery: Option<MtuDiscovery>, // Probes for packets bigger than BUF_SIZE
+    clock: Box<dyn Clock>,               // Time source for update() loop
+    previous: Instant,                   // For update() loop
+    lag: u128,                           // For update() loop
+    terminate: bool,                     // For gracefully exiting
+    pub action_trace: Vec<Action>,       // Optional trace for Debugging
 }
 
 impl UnetClient {
@@ -53,45 +83,72 @@ impl UnetClient {
     }
 
     pub fn from_config(mut config: ClientConfig) -> io::Result<Self> {
-        let target = config.target;
+        let target = canonical_addr(config.target);
 
-        let network = if let Some(virtual_network) = config.virtual_network.take() {
-            Virtual(virtual_network)
+        let transport = if let Some(transport) = config.transport.take() {
+            transport
         } else {
-            let socket = UdpSocket::bind("0.0.0.0:0")?;
-            socket.set_nonblocking(true)?;
-            socket.connect(target)?;
-            Real(socket)
+            // Bind the same address family as the server, so IPv6 servers are reachable
+            let bind_addr = match target {
+                SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+                SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+            };
+            Box::new(UdpTransport::bind(&[bind_addr], false)?)
         };
 
+        let clock = config.clock.take().unwrap_or_else(|| Box::new(SystemClock));
+
         let mut client_id = UnetId::new();
         if let Some(id) = config.id {
             client_id = id;
         }
 
+        let stats = StatsTracker::new(config.ping_frequency, config.tps);
+        let congestion = config
+            .congestion_control
+            .map(|congestion_config| CongestionController::new(congestion_config, config.tps));
+        let mtu_discovery = config
+            .mtu_discovery
+            .map(|mtu_config| MtuDiscovery::new(mtu_config, config.tps));
+
         let client = Self {
             id: client_id,
-            target: target.to_socket_addrs().unwrap().next().unwrap(),
-            network,
+            target,
+            transport,
             state: ClientState::SendingConnectionRequest,
             send_queue: VecDeque::new(),
             config,
             ticks_since_last_packet_sent: Tick { value: 0.0 },
             ticks_since_last_packet_received: Tick { value: 0.0 },
+            ticks_since_connect_started: Tick { value: 0.0 },
+            handshake_packet_due: true,
             sequence: 0,
-            previous: Instant::now(),
+            session_id: None,
+            stale_session_id: None,
+            cookie: Cookie::default(),
+            queue_position: None,
+            replay_window: ReplayWindow::new(),
+            secret: rand::random(),
+            stats,
+            congestion,
+            reconnect_attempt: 0,
+            reconnect_in: None,
+            events: VecDeque::new(),
+            mtu_discovery,
+            previous: clock.now(),
+            clock,
             lag: 0,
             terminate: false,
             action_trace: vec![],
         };
 
-        connecting_dbg(client_id, target.to_socket_addrs().unwrap().next().unwrap());
+        connecting_dbg(client_id, target);
         Ok(client)
     }
 
     pub fn update(&mut self) -> bool {
-        let now = Instant::now();
-        let elapsed = self.previous.elapsed();
+        let now = self.clock.now();
+        let elapsed = now - self.previous;
 
         self.lag += elapsed.as_millis();
         if self.lag >= self.config.ms_per_tick {
@@ -101,7 +158,7 @@ impl UnetClient {
 
             self.lag -= self.config.ms_per_tick;
         } else {
-            sleep(Duration::from_millis(
+            self.clock.sleep(Duration::from_millis(
                 (self.config.ms_per_tick - self.lag) as u64,
             ));
         }
@@ -118,29 +175,81 @@ impl UnetClient {
 
         self.receive_packets();
         self.send_packets();
+        self.stats.tick(self.clock.now());
+        if let Some(congestion) = &mut self.congestion {
+            congestion.tick(&self.stats.stats());
+        }
+        if let Some(mtu_discovery) = &mut self.mtu_discovery {
+            mtu_discovery.tick();
+        }
         self.print_state();
 
-        if !self.check_server_response_ok() {
-            disconnect_dbg(self.id, self.target, "Server not responding".to_string());
-            self.exit();
+        if !matches!(self.state, ClientState::Disconnected(..)) {
+            if !self.check_server_response_ok() {
+                self.time_out("Server not responding");
+            } else if !self.check_connect_timeout_ok() {
+                self.time_out("Connecting took too long");
+            }
         }
 
         self.ticks_since_last_packet_sent.value += 1.0;
         self.ticks_since_last_packet_received.value += 1.0;
+        self.ticks_since_connect_started.value += 1.0;
 
         true
     }
 
+    pub fn poll_event(&mut self) -> Option<ClientEvent> {
+        self.events.pop_front()
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.transport.local_addr()
+    }
+
+    /// Id the server assigned to us, None until we're connected.
+    pub fn session_id(&self) -> Option<UnetId> {
+        self.session_id
+    }
+
+    /// 1 is next in line, None unless we're waiting for a slot.
+    pub fn queue_position(&self) -> Option<u32> {
+        self.queue_position
+    }
+
+    pub fn stats(&self) -> NetworkStats {
+        self.stats.stats()
+    }
+
+    pub fn congestion_mode(&self) -> Option<CongestionMode> {
+        Some(self.congestion.as_ref()?.mode())
+    }
+
+    /// Packets per second `send_queue` is currently drained at, None if it isn't throttled.
+    pub fn allowed_send_rate(&self) -> Option<f32> {
+        Some(self.congestion.as_ref()?.allowed_rate())
+    }
+
+    /// Largest packet that's known to reach the server, `BUF_SIZE` unless discovered otherwise.
+    pub fn mtu(&self) -> usize {
+        self.mtu_discovery
+            .as_ref()
+            .map_or(BUF_SIZE, MtuDiscovery::mtu)
+    }
+
     pub fn send(&mut self, packet: Packet) {
         self.send_queue.push_back(packet)
     }
 
     fn internal_send(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.network.send(buf)
+        self.transport.send_to(buf, self.target)
     }
 
     pub fn send_packet(&mut self, mut packet: Packet) -> io::Result<usize> {
         packet.set_sequence(self.sequence);
+        if let Some(session_id) = self.session_id {
+            packet.set_client_id(session_id);
+        }
 
         if self.config.send_debug {
             send_dbg(packet, None, None);
@@ -152,6 +261,7 @@ impl UnetClient {
 
         let bytes = packet.as_bytes();
         let res = self.internal_send(&bytes);
+        self.stats.packet_sent(bytes.len());
         self.ticks_since_last_packet_sent.value = 0.0;
         self.sequence += 1;
 
@@ -161,22 +271,61 @@ impl UnetClient {
     pub fn send_packets(&mut self) {
         match self.state {
             ClientState::SendingConnectionRequest => {
-                self.send_connection_request_packet().unwrap();
+                if self.should_send_handshake_packet() {
+                    self.send_connection_request_packet().unwrap();
+                    self.handshake_packet_due = false;
+                }
             }
             ClientState::SendingConnectionResponse => {
-                self.send_connection_response_packet().unwrap();
+                if self.should_send_handshake_packet() {
+                    self.send_connection_response_packet().unwrap();
+                    self.handshake_packet_due = false;
+                }
+            }
+            ClientState::Queued => {
+                // Lets the server know we're still waiting
+                if self.should_send_keep_alive() {
+                    self.send_keep_alive_packet().unwrap();
+                }
             }
             ClientState::Connected => {
+                if let Some(ping_id) = self.stats.next_ping(self.clock.now()) {
+                    self.send_ping_packet(ping_id).unwrap();
+                }
+
+                let probe = self
+                    .mtu_discovery
+                    .as_mut()
+                    .and_then(MtuDiscovery::next_probe);
+                if let Some(size) = probe {
+                    self.send_mtu_probe_packet(size).unwrap();
+                }
+
                 if self.send_queue.is_empty() && self.should_send_keep_alive() {
                     self.send_keep_alive_packet().unwrap();
                     return;
                 }
 
-                while let Some(packet) = self.send_queue.pop_front() {
+                while !self.send_queue.is_empty() {
+                    if let Some(congestion) = &mut self.congestion {
+                        if !congestion.try_send() {
+                            break;
+                        }
+                    }
+
+                    let packet = self.send_queue.pop_front().unwrap();
                     self.send_packet(packet).unwrap();
                 }
             }
             ClientState::Disconnected(reason) => {
+                if let Some(reconnect_in) = &mut self.reconnect_in {
+                    reconnect_in.value -= 1.0;
+                    if reconnect_in.value <= 0.0 {
+                        self.reconnect();
+                    }
+                    return;
+                }
+
                 match reason {
                     DisconnectReason::Timeout => {
                         disconnect_dbg(self.id, self.target, "Client timed out".to_string());
@@ -191,6 +340,22 @@ impl UnetClient {
                             "Kicked for spamming the server".to_string(),
                         );
                     }
+                    DisconnectReason::Banned => {
+                        disconnect_dbg(self.id, self.target, "Banned from the server".to_string());
+                    }
+                    DisconnectReason::ServerShutdown => {
+                        disconnect_dbg(self.id, self.target, "Server shut down".to_string());
+                    }
+                    DisconnectReason::Rejected => {
+                        disconnect_dbg(self.id, self.target, "Rejected by the server".to_string());
+                    }
+                    DisconnectReason::Evicted => {
+                        disconnect_dbg(
+                            self.id,
+                            self.target,
+                            "Evicted to make room for a priority client".to_string(),
+                        );
+                    }
                     DisconnectReason::ConnectionResetByPeer => {
                         disconnect_dbg(
                             self.id,
@@ -199,23 +364,112 @@ impl UnetClient {
                         );
                     }
                 }
-                self.exit()
+
+                if !self.schedule_reconnect(reason) {
+                    self.exit()
+                }
             }
         }
     }
 
+    /// Returns false if the reconnect policy says we're done.
+    fn schedule_reconnect(&mut self, reason: DisconnectReason) -> bool {
+        let Some(policy) = &self.config.reconnect else {
+            return false;
+        };
+
+        let out_of_attempts = policy
+            .max_attempts
+            .is_some_and(|max_attempts| self.reconnect_attempt >= max_attempts);
+        if !policy.is_retryable(reason) || out_of_attempts {
+            self.events
+                .push_back(ClientEvent::ReconnectFailed { reason });
+            return false;
+        }
+
+        self.reconnect_attempt += 1;
+        let delay = policy.delay(self.reconnect_attempt);
+        self.reconnect_in = Some(Tick::from_duration(delay, self.config.tps));
+        self.events.push_back(ClientEvent::ReconnectScheduled {
+            attempt: self.reconnect_attempt,
+            delay,
+            reason,
+        });
+
+        true
+    }
+
+    /// Starts the handshake over, as if the client was just created.
+    fn reconnect(&mut self) {
+        self.reconnect_in = None;
+        self.state = ClientState::SendingConnectionRequest;
+        self.sequence = 0;
+        self.stale_session_id = self.session_id.take().or(self.stale_session_id);
+        self.replay_window = ReplayWindow::new();
+        self.cookie = Cookie::default();
+        self.queue_position = None;
+        self.secret = rand::random();
+        self.ticks_since_last_packet_sent.value = 0.0;
+        self.ticks_since_last_packet_received.value = 0.0;
+        self.ticks_since_connect_started.value = 0.0;
+        self.handshake_packet_due = true;
+        self.stats = StatsTracker::new(self.config.ping_frequency, self.config.tps);
+        self.congestion = self
+            .config
+            .congestion_control
+            .map(|congestion_config| CongestionController::new(congestion_config, self.config.tps));
+        self.mtu_discovery = self
+            .config
+            .mtu_discovery
+            .map(|mtu_config| MtuDiscovery::new(mtu_config, self.config.tps));
+
+        connecting_dbg(self.id, self.target);
+        self.events.push_back(ClientEvent::Reconnecting {
+            attempt: self.reconnect_attempt,
+        });
+    }
+
     pub fn send_connection_request_packet(&mut self) -> io::Result<usize> {
-        self.send_packet(Packet::ConnectionRequest(ConnectionRequest::new(self.id)))
+        let mut connection_request = ConnectionRequest::new(self.id);
+        connection_request.priority = self.config.priority;
+        self.send_packet(Packet::ConnectionRequest(connection_request))
     }
 
     pub fn send_connection_response_packet(&mut self) -> io::Result<usize> {
-        self.send_packet(Packet::ChallengeResponse(ChallengeResponse::new(self.id)))
+        let mut challenge_response = ChallengeResponse::new(self.id, self.secret, self.cookie);
+        challenge_response.priority = self.config.priority;
+        challenge_response.user_data = self.config.user_data;
+        self.send_packet(Packet::ChallengeResponse(challenge_response))
     }
 
     pub fn send_keep_alive_packet(&mut self) -> io::Result<usize> {
         self.send_packet(Packet::KeepAlive(KeepAlive::new(self.id)))
     }
 
+    pub fn send_ping_packet(&mut self, ping_id: u64) -> io::Result<usize> {
+        self.send_packet(Packet::Ping(Ping::new(self.id, ping_id)))
+    }
+
+    pub fn send_pong_packet(&mut self, ping_id: u64) -> io::Result<usize> {
+        self.send_packet(Packet::Pong(Pong::new(self.id, ping_id)))
+    }
+
+    pub fn send_mtu_probe_packet(&mut self, size: u16) -> io::Result<usize> {
+        self.send_packet(Packet::MtuProbe(MtuProbe::new(self.id, size)))
+    }
+
+    pub fn send_mtu_probe_ack_packet(&mut self, size: u16) -> io::Result<usize> {
+        self.send_packet(Packet::MtuProbeAck(MtuProbeAck::new(self.id, size)))
+    }
+
+    pub fn send_path_response_packet(&mut self, challenge: u64) -> io::Result<usize> {
+        self.send_packet(Packet::PathResponse(PathResponse::new(
+            self.id,
+            challenge,
+            self.secret,
+        )))
+    }
+
     pub fn send_disconnect_packet(&mut self) -> io::Result<usize> {
         self.send_packet(Packet::Disconnect(Disconnect::new(
             self.id,
@@ -224,57 +478,143 @@ impl UnetClient {
     }
 
     fn receive(&self, buf: &mut [u8]) -> Option<usize> {
-        let (n, _from) = self.network.recv_from(buf)?;
-        Some(n)
+        loop {
+            let (n, from) = self.transport.recv_from(buf).ok()?;
+            if from == self.target {
+                return Some(n);
+            }
+            // Not from the server we're talking to, ignore it
+        }
     }
 
-    fn receive_packet(&self) -> Option<Packet> {
-        let mut buf: [u8; BUF_SIZE] = [0; BUF_SIZE];
+    fn receive_packet(&mut self) -> Option<(Option<Packet>, usize)> {
+        let mut buf: [u8; MAX_PACKET_SIZE] = [0; MAX_PACKET_SIZE];
         let n = self.receive(&mut buf)?;
-        assert_ne!(n, 0);
-        Packet::from_bytes(&buf[..n])
+        self.stats.packet_received(n);
+        Some((Packet::from_bytes(&buf[..n]), n))
     }
 
     fn receive_packets(&mut self) {
-        while let Some(packet) = self.receive_packet() {
+        while let Some((packet, size)) = self.receive_packet() {
+            // Garbled or of a kind we don't know, the rest of the datagrams are still worth a look
+            let Some(packet) = packet else {
+                self.stats.packet_dropped();
+                continue;
+            };
+
             if self.config.action_trace {
                 self.action_trace.push(Action::ReceivePacket(packet.kind()))
             }
 
-            self.handle_packet(packet);
+            self.handle_packet(packet, size);
         }
     }
 
-    fn handle_packet(&mut self, packet: Packet) {
+    fn handle_packet(&mut self, packet: Packet, size: usize) {
         if self.config.recv_debug {
             recv_dbg(packet, None, None);
         }
 
+        // Still in flight from before we reconnected, e.g. keep-alives that would otherwise pass
+        // for the answer to our new handshake
+        if self.stale_session_id == Some(packet.header().client_id) {
+            return;
+        }
+
+        // Handshake packets come before the server keeps any state for us, so they aren't
+        // sequenced yet
+        if self.state == ClientState::Connected && !self.accept_sequence(packet) {
+            return;
+        }
+
         self.reset_timeout();
         match packet {
-            Packet::ChallengeRequest => {
-                if self.state == ClientState::SendingConnectionRequest {
+            Packet::ChallengeRequest(challenge_request) => match self.state {
+                ClientState::SendingConnectionRequest => {
+                    self.cookie = challenge_request.cookie;
                     self.state = ClientState::SendingConnectionResponse;
+                    self.handshake_packet_due = true;
                 }
-            }
+                ClientState::SendingConnectionResponse => {
+                    // Ours expired, answer the new one
+                    self.cookie = challenge_request.cookie;
+                    self.handshake_packet_due = true;
+                }
+                _ => {}
+            },
             Packet::Disconnect(disconnect) => {
                 if !matches!(self.state, ClientState::Disconnected(..)) {
                     self.state = ClientState::Disconnected(disconnect.reason)
                 }
             }
+            Packet::Queued(queued) => {
+                if matches!(
+                    self.state,
+                    ClientState::SendingConnectionResponse | ClientState::Queued
+                ) {
+                    self.state = ClientState::Queued;
+                    self.queue_position = Some(queued.position);
+                }
+            }
             Packet::KeepAlive(keep_alive) => {
-                if self.state == ClientState::SendingConnectionResponse {
+                if matches!(
+                    self.state,
+                    ClientState::SendingConnectionResponse | ClientState::Queued
+                ) {
                     self.state = ClientState::Connected;
+                    self.queue_position = None;
+                    self.session_id = Some(keep_alive.header.client_id);
+                    self.accept_sequence(packet);
                     connected_dbg(self.id, self.target);
+
+                    if self.reconnect_attempt > 0 {
+                        self.events.push_back(ClientEvent::Reconnected {
+                            attempt: self.reconnect_attempt,
+                        });
+                        self.reconnect_attempt = 0;
+                    }
                 }
             }
             Packet::Data(_) => {}
-            _ => {
-                panic!("Client should never get this packet: {packet:#?}");
+            Packet::Ping(ping) => {
+                self.send_pong_packet(ping.ping_id).unwrap();
+            }
+            Packet::Pong(pong) => {
+                self.stats.pong_received(pong.ping_id, self.clock.now());
+            }
+            Packet::MtuProbe(mtu_probe) => {
+                // Only ack probes that made it through whole
+                if mtu_probe.size as usize == size {
+                    self.send_mtu_probe_ack_packet(mtu_probe.size).unwrap();
+                }
+            }
+            Packet::MtuProbeAck(mtu_probe_ack) => {
+                if let Some(mtu_discovery) = &mut self.mtu_discovery {
+                    mtu_discovery.ack_received(mtu_probe_ack.size);
+                }
+            }
+            Packet::PathChallenge(path_challenge) => {
+                // Our address changed, prove it's still us
+                if self.state == ClientState::Connected {
+                    self.send_path_response_packet(path_challenge.challenge)
+                        .unwrap();
+                }
+            }
+            // Only ever sent by clients
+            Packet::ConnectionRequest(_)
+            | Packet::ChallengeResponse(_)
+            | Packet::PathResponse(_) => {
+                self.stats.packet_dropped();
             }
         };
     }
 
+    fn accept_sequence(&mut self, packet: Packet) -> bool {
+        let header = packet.header();
+        let check = self.replay_window.receive(header.sequence);
+        self.stats.replay_checked(check)
+    }
+
     fn reset_timeout(&mut self) {
         self.ticks_since_last_packet_received.value = 0.0;
     }
@@ -288,6 +628,32 @@ impl UnetClient {
         }
     }
 
+    fn check_connect_timeout_ok(&self) -> bool {
+        // The server times out the queue itself
+        if matches!(self.state, ClientState::Connected | ClientState::Queued) {
+            return true;
+        }
+
+        if let Some(connect_timeout) = self.config.connect_timeout {
+            self.ticks_since_connect_started <= connect_timeout
+        } else {
+            true
+        }
+    }
+
+    fn time_out(&mut self, message: &str) {
+        disconnect_dbg(self.id, self.target, message.to_string());
+        self.state = ClientState::Disconnected(DisconnectReason::Timeout);
+        if !self.schedule_reconnect(DisconnectReason::Timeout) {
+            self.exit();
+        }
+    }
+
+    fn should_send_handshake_packet(&self) -> bool {
+        self.handshake_packet_due
+            || self.ticks_since_last_packet_sent >= self.config.handshake_resend_interval
+    }
+
     fn should_send_keep_alive(&self) -> bool {
         self.ticks_since_last_packet_sent >= DEFAULT_KEEP_ALIVE_FREQUENCY
     }
@@ -311,7 +677,7 @@ pub fn connecting_dbg(id: UnetId, to: SocketAddr) {
     )
 }
 
-pub fn connected_dbg(id: UnetId, to: SocketAddr) {
+pub fn connected_dbg(id: UnetId, _to: SocketAddr) {
     println!(
         "{} connected!",
         format!("{:16x}", id.0).truecolor(BLUE.r, BLUE.g, BLUE.b),
@@ -336,5 +702,11 @@ mod tests {
     fn preview() {
         connecting_dbg(UnetId(u64::MAX), "255.255.255.255:65535".parse().unwrap());
         connecting_dbg(UnetId(0xdeadbeef), "0.0.0.0:0".parse().unwrap());
+        connected_dbg(UnetId(0xdeadbeef), "0.0.0.0:0".parse().unwrap());
+        disconnect_dbg(
+            UnetId(0xdeadbeef),
+            "0.0.0.0:0".parse().unwrap(),
+            "Server was full".to_string(),
+        );
     }
 }
@@ -0,0 +1,22 @@
+use crate::packet::disconnect::DisconnectReason;
+use std::time::Duration;
+
+/// Things that happened to the client, see `UnetClient::poll_event()`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ClientEvent {
+    ReconnectScheduled {
+        attempt: u32,
+        delay: Duration,
+        reason: DisconnectReason, // Why we got disconnected
+    },
+    Reconnecting {
+        attempt: u32,
+    },
+    Reconnected {
+        attempt: u32,
+    },
+    // Not retryable, or out of attempts, the client stops after this
+    ReconnectFailed {
+        reason: DisconnectReason,
+    },
+}
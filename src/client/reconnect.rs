@@ -0,0 +1,92 @@
+use crate::packet::disconnect::DisconnectReason;
+use std::time::Duration;
+
+/// How `UnetClient` retries the handshake after getting disconnected.
+///
+/// The n-th attempt waits `initial_delay * multiplier^(n - 1)`, capped at `max_delay`, give or
+/// take up to `jitter` of that (e.g. 0.1 for 10%) so clients dropped at the same time don't all
+/// come back at once.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReconnectPolicy {
+    pub max_attempts: Option<u32>, // None keeps trying forever
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f32,
+    pub jitter: f32,
+    pub retryable: Vec<DisconnectReason>, // Any other reason ends the client like before
+}
+
+impl ReconnectPolicy {
+    pub fn new() -> Self {
+        Self {
+            max_attempts: Some(5),
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.1,
+            retryable: vec![
+                DisconnectReason::Timeout,
+                DisconnectReason::ConnectionResetByPeer,
+            ],
+        }
+    }
+
+    pub fn is_retryable(&self, reason: DisconnectReason) -> bool {
+        self.retryable.contains(&reason)
+    }
+
+    /// How long to wait before reconnect attempt number `attempt`, starting from 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = (self.multiplier as f64).powi(exponent);
+        let delay = (self.initial_delay.as_secs_f64() * backoff).min(self.max_delay.as_secs_f64());
+        let delay = Duration::from_secs_f64(delay);
+        if self.jitter <= 0.0 {
+            return delay;
+        }
+
+        let jitter = self.jitter as f64 * (rand::random::<f64>() * 2.0 - 1.0);
+        delay.mul_f64((1.0 + jitter).max(0.0))
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::reconnect::ReconnectPolicy;
+    use crate::packet::disconnect::DisconnectReason;
+    use std::time::Duration;
+
+    #[test]
+    fn exponential_backoff() {
+        let mut policy = ReconnectPolicy::new();
+        policy.jitter = 0.0;
+        assert_eq!(policy.delay(1), Duration::from_millis(500));
+        assert_eq!(policy.delay(2), Duration::from_secs(1));
+        assert_eq!(policy.delay(3), Duration::from_secs(2));
+        assert_eq!(policy.delay(100), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn jitter_is_bounded() {
+        let policy = ReconnectPolicy::new();
+        for _ in 0..1000 {
+            let delay = policy.delay(3);
+            assert!(delay >= Duration::from_millis(1799));
+            assert!(delay <= Duration::from_millis(2201));
+        }
+    }
+
+    #[test]
+    fn server_full_and_spam_are_not_retried_by_default() {
+        let policy = ReconnectPolicy::new();
+        assert!(policy.is_retryable(DisconnectReason::Timeout));
+        assert!(!policy.is_retryable(DisconnectReason::ServerFull));
+        assert!(!policy.is_retryable(DisconnectReason::Spam));
+    }
+}
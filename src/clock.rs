@@ -0,0 +1,98 @@
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub trait Clock: Debug + Send {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+/// Wall-clock time, `sleep()` blocks the current thread.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
+/// Simulated time that only moves when told to. Clones share the same time, so a single
+/// `ManualClock` can drive a server and any number of clients in lockstep.
+/// `sleep()` advances the clock instead of blocking.
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed_nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed_nanos: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.elapsed_nanos.load(Ordering::SeqCst))
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clock::{Clock, ManualClock};
+    use std::time::Duration;
+
+    #[test]
+    fn manual_clock_advance() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        clock.advance(Duration::from_millis(50));
+        assert_eq!(clock.now() - start, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn manual_clock_sleep_advances() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        clock.sleep(Duration::from_secs(10));
+        assert_eq!(clock.now() - start, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn manual_clock_clones_share_time() {
+        let clock = ManualClock::new();
+        let other = clock.clone();
+        other.advance(Duration::from_millis(20));
+        assert_eq!(clock.elapsed(), Duration::from_millis(20));
+        assert_eq!(clock.now(), other.now());
+    }
+}
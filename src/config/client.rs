@@ -1,19 +1,34 @@
-use crate::network::VirtualNetwork;
+use crate::client::reconnect::ReconnectPolicy;
+use crate::clock::Clock;
+use crate::congestion::CongestionConfig;
+use crate::mtu::MtuConfig;
+use crate::network::Transport;
 use crate::packet::UnetId;
 use crate::tick::Tick;
+use crate::user_data::UserData;
 use crate::{
-    DEFAULT_KEEP_ALIVE_FREQUENCY, DEFAULT_SERVER_ADDR, DEFAULT_SERVER_NOT_RESPONDING_TIMEOUT,
+    DEFAULT_CONNECT_TIMEOUT, DEFAULT_HANDSHAKE_RESEND_INTERVAL, DEFAULT_KEEP_ALIVE_FREQUENCY,
+    DEFAULT_PING_FREQUENCY, DEFAULT_SERVER_ADDR, DEFAULT_SERVER_NOT_RESPONDING_TIMEOUT,
     DEFAULT_TPS,
 };
 use std::net::SocketAddr;
 
 #[derive(Debug)]
 pub struct ClientConfig {
-    pub virtual_network: Option<VirtualNetwork>,
-    pub id: Option<UnetId>,
+    pub transport: Option<Box<dyn Transport>>,
+    pub clock: Option<Box<dyn Clock>>,
+    pub id: Option<UnetId>, // Identifies us to people, the server assigns the session id
     pub target: SocketAddr,
+    pub priority: u8, // Above 0 lets us into reserved slots, if the server agrees
+    pub user_data: UserData, // Handed to the server's AcceptHook, e.g. an auth ticket
     pub server_not_responding_timeout: Option<Tick>,
+    pub connect_timeout: Option<Tick>, // Whole handshake has to be done by then
+    pub handshake_resend_interval: Tick, // Handshake packets are repeated this often until answered
     pub keep_alive_frequency: Tick,
+    pub ping_frequency: Tick, // How often to measure the round trip time
+    pub congestion_control: Option<CongestionConfig>, // None means sending is never throttled
+    pub mtu_discovery: Option<MtuConfig>, // None means the MTU stays at BUF_SIZE
+    pub reconnect: Option<ReconnectPolicy>, // None means the client exits once disconnected
     pub tps: f32,
     pub ms_per_tick: u128,
     pub recv_debug: bool,
@@ -25,7 +40,10 @@ impl ClientConfig {
     pub fn new() -> Self {
         let target = DEFAULT_SERVER_ADDR;
         let server_not_responding_timeout = Some(DEFAULT_SERVER_NOT_RESPONDING_TIMEOUT);
+        let connect_timeout = Some(DEFAULT_CONNECT_TIMEOUT);
+        let handshake_resend_interval = DEFAULT_HANDSHAKE_RESEND_INTERVAL;
         let keep_alive_frequency = DEFAULT_KEEP_ALIVE_FREQUENCY;
+        let ping_frequency = DEFAULT_PING_FREQUENCY;
         let tps = DEFAULT_TPS;
         let ms_per_tick = (1000.0 / tps) as u128;
 
@@ -34,11 +52,20 @@ impl ClientConfig {
         let action_trace = false;
 
         Self {
-            virtual_network: None,
+            transport: None,
+            clock: None,
             id: None,
             target,
+            priority: 0,
+            user_data: UserData::default(),
             server_not_responding_timeout,
+            connect_timeout,
+            handshake_resend_interval,
             keep_alive_frequency,
+            ping_frequency,
+            congestion_control: None,
+            mtu_discovery: None,
+            reconnect: None,
             tps,
             ms_per_tick,
             recv_debug,
@@ -1,20 +1,48 @@
-use crate::network::VirtualNetwork;
+use crate::clock::Clock;
+use crate::congestion::CongestionConfig;
+use crate::mtu::MtuConfig;
+use crate::network::Transport;
+use crate::server::accept::AcceptHook;
+use crate::server::ban::BanList;
+use crate::server::rate_limit::RateLimitConfig;
+use crate::server::reserved_slots::ReservedSlotsConfig;
+use crate::server::spam::SpamPolicy;
+use crate::server::waiting_queue::WaitingQueueConfig;
 use crate::{
-    Tick, DEFAULT_CLIENT_CONNECTION_TIMEOUT, DEFAULT_KEEP_ALIVE_FREQUENCY, DEFAULT_SERVER_ADDR,
-    DEFAULT_TPS,
+    Tick, DEFAULT_CLIENT_CONNECTION_TIMEOUT, DEFAULT_HANDSHAKE_TIMEOUT,
+    DEFAULT_KEEP_ALIVE_FREQUENCY, DEFAULT_PING_FREQUENCY, DEFAULT_SERVER_ADDR, DEFAULT_TPS,
+    MAX_CONNECTIONS,
 };
 use std::net::SocketAddr;
+use std::time::Duration;
 
 #[derive(Debug)]
 pub struct ServerConfig {
-    pub virtual_network: Option<VirtualNetwork>,
+    pub transport: Option<Box<dyn Transport>>,
+    pub clock: Option<Box<dyn Clock>>,
     pub addr: SocketAddr,
+    pub additional_addrs: Vec<SocketAddr>, // Also listen on these, e.g. one IPv4 and one IPv6 address
+    pub dual_stack: bool,                  // IPv6 sockets accept IPv4 traffic too
+    pub max_connections: usize,
+    pub reserved_slots: Option<ReservedSlotsConfig>, // None means every slot is first come, first served
+    pub waiting_queue: Option<WaitingQueueConfig>,   // None means clients are turned away when full
+    pub accept_hook: Option<Box<dyn AcceptHook>>, // None means everyone who completes the handshake gets in
     pub client_connection_timeout: Tick,
+    pub handshake_timeout: Tick, // Challenges have to be answered within this
+    pub shutdown_timeout: Duration, // How long shutdown() waits for send queues to drain
     pub keep_alive_frequency: Tick,
+    pub ping_frequency: Tick, // How often to measure the round trip time
+    pub congestion_control: Option<CongestionConfig>, // None means sending is never throttled
+    pub mtu_discovery: Option<MtuConfig>, // None means the MTU stays at BUF_SIZE
+    pub rate_limit: Option<RateLimitConfig>, // Per-IP limits on handshakes and unknown traffic
+    pub ban_list: BanList,    // See BanList::load()
+    // Follow clients to a new address once they prove they know the connection's secret. Off by
+    // default: the secret goes out in the clear with the handshake, so anyone who saw that could
+    // take over the connection.
+    pub connection_migration: bool,
     pub tps: f32,
     pub ms_per_tick: u128,
-    max_rolling_packets_per_second: Option<f32>,
-    pub max_rolling_packets_per_tick: Option<f32>, // If this is not specified, clients can spam as much as they want
+    pub spam_policy: Option<SpamPolicy>, // If this is not specified, clients can spam as much as they want
     pub recv_debug: bool,
     pub send_debug: bool,
 }
@@ -23,32 +51,50 @@ impl ServerConfig {
     pub fn new() -> Self {
         let addr = DEFAULT_SERVER_ADDR;
         let client_connection_timeout = DEFAULT_CLIENT_CONNECTION_TIMEOUT;
+        let handshake_timeout = DEFAULT_HANDSHAKE_TIMEOUT;
         let keep_alive_frequency = DEFAULT_KEEP_ALIVE_FREQUENCY;
+        let ping_frequency = DEFAULT_PING_FREQUENCY;
         let tps = DEFAULT_TPS;
         let ms_per_tick = (1000.0 / tps) as u128;
-        let max_rolling_packets_per_tick = Some(3.0);
-        let max_rolling_packets_per_second = Some(max_rolling_packets_per_second(
-            max_rolling_packets_per_tick.unwrap(),
-            tps,
-        ));
+        let spam_policy = Some(SpamPolicy::new());
 
         let recv_debug = false;
         let send_debug = false;
 
         Self {
-            virtual_network: None,
+            transport: None,
+            clock: None,
             addr,
+            additional_addrs: vec![],
+            dual_stack: false,
+            max_connections: MAX_CONNECTIONS,
+            reserved_slots: None,
+            waiting_queue: None,
+            accept_hook: None,
             client_connection_timeout,
+            handshake_timeout,
+            shutdown_timeout: Duration::from_secs(1),
             keep_alive_frequency,
+            ping_frequency,
+            congestion_control: None,
+            mtu_discovery: None,
+            rate_limit: None,
+            ban_list: BanList::new(),
+            connection_migration: false,
             tps,
             ms_per_tick,
-            max_rolling_packets_per_second,
-            max_rolling_packets_per_tick,
+            spam_policy,
             recv_debug,
             send_debug,
         }
     }
 
+    pub fn listen_addrs(&self) -> Vec<SocketAddr> {
+        let mut addrs = vec![self.addr];
+        addrs.extend_from_slice(&self.additional_addrs);
+        addrs
+    }
+
     pub fn test() -> Self {
         todo!()
     }
@@ -59,36 +105,3 @@ impl Default for ServerConfig {
         Self::new()
     }
 }
-
-fn max_rolling_packets_per_second(max_packets_per_tick: f32, ups: f32) -> f32 {
-    max_packets_per_tick * ups
-}
-
-fn max_rolling_packets_per_tick(max_packets_per_second: f32, ups: f32) -> f32 {
-    max_packets_per_second / ups
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::config::server::{max_rolling_packets_per_second, max_rolling_packets_per_tick};
-
-    #[test]
-    fn max_rolling_packets_per_second_calculation() {
-        let ups = 20.0;
-        let max_packets_per_tick = 2.0;
-        assert_eq!(
-            max_rolling_packets_per_second(max_packets_per_tick, ups),
-            40.0
-        );
-    }
-
-    #[test]
-    fn max_rolling_packets_per_tick_calculation() {
-        let ups = 20.0;
-        let max_packets_per_second = 50.0;
-        assert_eq!(
-            max_rolling_packets_per_tick(max_packets_per_second, ups),
-            2.5
-        );
-    }
-}
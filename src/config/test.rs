@@ -1,26 +1,110 @@
+use crate::client::UnetClient;
+use crate::clock::{Clock, ManualClock, SystemClock};
 use crate::config::client::ClientConfig;
 use crate::config::server::ServerConfig;
-use crate::network::VirtualNetwork;
-use std::sync::mpsc::channel;
+use crate::network::link_conditioner::LinkConditionerConfig;
+use crate::network::VirtualHub;
+use crate::server::UnetServer;
+use std::borrow::BorrowMut;
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::time::Duration;
 
+/// `conditioned_test_config()` on the system clock, over links that let everything through.
 pub fn test_config() -> (ServerConfig, ClientConfig) {
-    let (server_tx, server_rx) = channel();
-    let (client_tx, client_rx) = channel();
-
-    let client_network = VirtualNetwork {
-        tx: server_tx,
-        rx: client_rx,
-    };
-    let server_network = VirtualNetwork {
-        tx: client_tx,
-        rx: server_rx,
-    };
+    let link = LinkConditionerConfig::new();
+    conditioned_test_config(SystemClock, link, link, 0)
+}
+
+/// A server and `clients` clients all plugged into the same `VirtualHub`, each with its own
+/// address.
+pub fn multi_client_test_config(clients: usize) -> (ServerConfig, Vec<ClientConfig>) {
+    let hub = VirtualHub::new();
+    let server_config = test_server_config(&hub);
+
+    let client_configs = (0..clients)
+        .map(|i| {
+            let mut client_config = ClientConfig::new();
+            client_config.target = server_config.addr;
+            client_config.transport = Some(Box::new(hub.endpoint(test_client_addr(i)).unwrap()));
+            client_config
+        })
+        .collect();
+
+    (server_config, client_configs)
+}
 
+/// A server config plugged into `hub` at the default server address.
+pub fn test_server_config(hub: &VirtualHub) -> ServerConfig {
     let mut server_config = ServerConfig::new();
-    server_config.virtual_network = Some(server_network);
+    server_config.transport = Some(Box::new(hub.endpoint(server_config.addr).unwrap()));
+    server_config
+}
+
+pub fn test_server(hub: &VirtualHub) -> UnetServer {
+    UnetServer::from_config(test_server_config(hub)).unwrap()
+}
+
+/// One client per config, they start connecting on their first tick.
+pub fn connect_clients(client_configs: Vec<ClientConfig>) -> Vec<UnetClient> {
+    client_configs
+        .into_iter()
+        .map(|client_config| UnetClient::from_config(client_config).unwrap())
+        .collect()
+}
+
+/// A server and a client that both run on `clock`, every packet goes through a link conditioner
+/// seeded with `seed`.
+pub fn conditioned_test_config(
+    clock: impl Clock + Clone + 'static,
+    client_to_server: LinkConditionerConfig,
+    server_to_client: LinkConditionerConfig,
+    seed: u64,
+) -> (ServerConfig, ClientConfig) {
+    let hub = VirtualHub::with_clock(clock.clone());
+
+    let mut server_config = ServerConfig::new();
+    let server_network = hub
+        .conditioned_endpoint(server_config.addr, server_to_client, seed)
+        .unwrap();
+    server_config.transport = Some(Box::new(server_network));
+    server_config.clock = Some(Box::new(clock.clone()));
 
     let mut client_config = ClientConfig::new();
-    client_config.virtual_network = Some(client_network);
+    let client_network = hub
+        .conditioned_endpoint(test_client_addr(0), client_to_server, seed.wrapping_add(1))
+        .unwrap();
+    client_config.target = server_config.addr;
+    client_config.transport = Some(Box::new(client_network));
+    client_config.clock = Some(Box::new(clock));
 
     (server_config, client_config)
 }
+
+pub fn test_client_addr(i: usize) -> SocketAddr {
+    let ip = Ipv4Addr::new(10, (i >> 16) as u8, (i >> 8) as u8, i as u8);
+    SocketAddr::V4(SocketAddrV4::new(ip, 20000))
+}
+
+/// Updates `server` and `client` until `duration` has passed on `clock`.
+pub fn run(
+    clock: &ManualClock,
+    server: &mut UnetServer,
+    client: &mut UnetClient,
+    duration: Duration,
+) {
+    let end = clock.elapsed() + duration;
+    while clock.elapsed() < end {
+        server.update();
+        client.update();
+    }
+}
+
+/// Ticks every client and then the server, `ticks` times over.
+pub fn tick_all(server: &mut UnetServer, clients: &mut [impl BorrowMut<UnetClient>], ticks: usize) {
+    for _ in 0..ticks {
+        for client in clients.iter_mut() {
+            client.borrow_mut().tick();
+        }
+        server.tick();
+    }
+}
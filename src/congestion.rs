@@ -0,0 +1,198 @@
+use crate::stats::NetworkStats;
+use crate::tick::Tick;
+use std::time::Duration;
+
+// Good mode lasting this long halves the penalty
+const STABLE_GOOD_MODE: Duration = Duration::from_secs(10);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CongestionConfig {
+    pub good_rate: f32, // Packets per second while the connection is healthy
+    pub bad_rate: f32,  // Packets per second while it's congested
+    pub rtt_threshold: Duration,
+    pub loss_threshold: f32,   // Percentage
+    pub min_penalty: Duration, // Conditions must stay good this long before speeding up again
+    pub max_penalty: Duration,
+}
+
+impl CongestionConfig {
+    pub fn new() -> Self {
+        Self {
+            good_rate: 30.0,
+            bad_rate: 10.0,
+            rtt_threshold: Duration::from_millis(250),
+            loss_threshold: 5.0,
+            min_penalty: Duration::from_secs(1),
+            max_penalty: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Default for CongestionConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CongestionMode {
+    Good,
+    Bad,
+}
+
+/// Switches between a good and a bad send rate based on measured RTT and loss.
+///
+/// Dropping to bad mode happens right away. Getting back to good mode needs the connection to
+/// be healthy for the whole penalty time, which doubles every time good mode doesn't last and
+/// halves again while it does.
+#[derive(Clone, Debug)]
+pub struct CongestionController {
+    pub config: CongestionConfig,
+    mode: CongestionMode,
+    penalty: Tick,
+    ticks_in_good_conditions: Tick,
+    ticks_in_good_mode: Tick,
+    budget: f32, // Packets we're allowed to send right now
+    tps: f32,
+}
+
+impl CongestionController {
+    pub fn new(config: CongestionConfig, tps: f32) -> Self {
+        Self {
+            config,
+            mode: CongestionMode::Good,
+            penalty: Tick::from_duration(config.min_penalty, tps),
+            ticks_in_good_conditions: Tick { value: 0.0 },
+            ticks_in_good_mode: Tick { value: 0.0 },
+            budget: config.good_rate / tps,
+            tps,
+        }
+    }
+
+    pub fn mode(&self) -> CongestionMode {
+        self.mode
+    }
+
+    /// Packets per second the application may currently send.
+    pub fn allowed_rate(&self) -> f32 {
+        match self.mode {
+            CongestionMode::Good => self.config.good_rate,
+            CongestionMode::Bad => self.config.bad_rate,
+        }
+    }
+
+    /// Takes one packet out of this tick's budget, returns false if there's none left.
+    pub fn try_send(&mut self) -> bool {
+        if self.budget < 1.0 {
+            return false;
+        }
+
+        self.budget -= 1.0;
+        true
+    }
+
+    pub fn tick(&mut self, stats: &NetworkStats) {
+        let min_penalty = Tick::from_duration(self.config.min_penalty, self.tps);
+        let max_penalty = Tick::from_duration(self.config.max_penalty, self.tps);
+        let stable_good_mode = Tick::from_duration(STABLE_GOOD_MODE, self.tps);
+        let congested =
+            stats.rtt > self.config.rtt_threshold || stats.packet_loss > self.config.loss_threshold;
+
+        match self.mode {
+            CongestionMode::Good => {
+                self.ticks_in_good_mode.value += 1.0;
+                if congested {
+                    // Good mode didn't last, be more careful next time
+                    if self.ticks_in_good_mode < stable_good_mode {
+                        self.penalty.value = (self.penalty.value * 2.0).min(max_penalty.value);
+                    }
+                    self.mode = CongestionMode::Bad;
+                    self.ticks_in_good_conditions.value = 0.0;
+                } else if self.ticks_in_good_mode >= stable_good_mode {
+                    self.penalty.value = (self.penalty.value / 2.0).max(min_penalty.value);
+                    self.ticks_in_good_mode.value = 0.0;
+                }
+            }
+            CongestionMode::Bad => {
+                if congested {
+                    self.ticks_in_good_conditions.value = 0.0;
+                } else {
+                    self.ticks_in_good_conditions.value += 1.0;
+                }
+
+                if self.ticks_in_good_conditions >= self.penalty {
+                    self.mode = CongestionMode::Good;
+                    self.ticks_in_good_mode.value = 0.0;
+                }
+            }
+        }
+
+        // Unused budget carries over, up to one second worth of packets
+        let rate_per_tick = self.allowed_rate() / self.tps;
+        self.budget = (self.budget + rate_per_tick).min(self.allowed_rate().max(1.0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::congestion::{CongestionConfig, CongestionController, CongestionMode};
+    use crate::stats::NetworkStats;
+    use std::time::Duration;
+
+    fn congested() -> NetworkStats {
+        NetworkStats {
+            rtt: Duration::from_millis(500),
+            ..NetworkStats::default()
+        }
+    }
+
+    #[test]
+    fn drops_to_bad_mode_immediately() {
+        let mut controller = CongestionController::new(CongestionConfig::new(), 20.0);
+        assert_eq!(controller.mode(), CongestionMode::Good);
+        controller.tick(&congested());
+        assert_eq!(controller.mode(), CongestionMode::Bad);
+        assert_eq!(controller.allowed_rate(), 10.0);
+    }
+
+    #[test]
+    fn packet_loss_is_congestion() {
+        let mut controller = CongestionController::new(CongestionConfig::new(), 20.0);
+        let stats = NetworkStats {
+            packet_loss: 10.0,
+            ..NetworkStats::default()
+        };
+        controller.tick(&stats);
+        assert_eq!(controller.mode(), CongestionMode::Bad);
+    }
+
+    #[test]
+    fn recovers_after_penalty() {
+        let mut controller = CongestionController::new(CongestionConfig::new(), 20.0);
+        controller.tick(&congested());
+        // Went bad right away, so the penalty doubled to 2 seconds
+        for _ in 0..39 {
+            controller.tick(&NetworkStats::default());
+            assert_eq!(controller.mode(), CongestionMode::Bad);
+        }
+        controller.tick(&NetworkStats::default());
+        assert_eq!(controller.mode(), CongestionMode::Good);
+    }
+
+    #[test]
+    fn throttles_to_allowed_rate() {
+        let mut controller = CongestionController::new(CongestionConfig::new(), 20.0);
+        controller.tick(&congested());
+
+        let mut sent = 0;
+        for _ in 0..20 {
+            controller.tick(&congested());
+            while controller.try_send() {
+                sent += 1;
+            }
+        }
+
+        // One second in bad mode, plus whatever was left over from good mode
+        assert!((10..=12).contains(&sent), "{sent}");
+    }
+}
@@ -0,0 +1,233 @@
+use crate::packet::UnetId;
+use crate::tick::Tick;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+
+const MAC_SIZE: usize = 16; // Truncated HMAC-SHA256
+
+/// Handed out with the `ChallengeRequest` and echoed back in the `ChallengeResponse`, so the
+/// server doesn't need to remember anything about clients that haven't answered yet.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Cookie {
+    pub timestamp: u64, // Server tick it was issued at
+    pub mac: [u8; MAC_SIZE],
+}
+
+impl Cookie {
+    pub const SIZE: usize = size_of::<u64>() + MAC_SIZE;
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        assert_eq!(bytes.len(), Self::SIZE);
+
+        let timestamp = u64::from_be_bytes(bytes[..8].try_into().unwrap());
+        let mac = bytes[8..].try_into().unwrap();
+        Self { timestamp, mac }
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut output = vec![];
+        output.extend_from_slice(&self.timestamp.to_be_bytes());
+        output.extend_from_slice(&self.mac);
+        output
+    }
+}
+
+/// Issues and checks cookies bound to a client's id, address and priority. The key is random per
+/// server, so cookies don't survive a restart.
+#[derive(Clone)]
+pub struct CookieKey {
+    key: [u8; 32],
+}
+
+impl CookieKey {
+    pub fn new() -> Self {
+        Self {
+            key: rand::random(),
+        }
+    }
+
+    pub fn issue(&self, client_id: UnetId, addr: SocketAddr, priority: u8, now: Tick) -> Cookie {
+        let timestamp = now.value as u64;
+        Cookie {
+            timestamp,
+            mac: self.mac(client_id, addr, priority, timestamp),
+        }
+    }
+
+    /// True if we issued `cookie` to this client within the last `lifetime` ticks.
+    pub fn verify(
+        &self,
+        cookie: Cookie,
+        client_id: UnetId,
+        addr: SocketAddr,
+        priority: u8,
+        now: Tick,
+        lifetime: Tick,
+    ) -> bool {
+        let now = now.value as u64;
+        if cookie.timestamp > now || now - cookie.timestamp > lifetime.value as u64 {
+            return false;
+        }
+
+        let mut mac = self.hmac();
+        mac.update(&Self::message(client_id, addr, priority, cookie.timestamp));
+        mac.verify_truncated_left(&cookie.mac).is_ok()
+    }
+
+    fn mac(
+        &self,
+        client_id: UnetId,
+        addr: SocketAddr,
+        priority: u8,
+        timestamp: u64,
+    ) -> [u8; MAC_SIZE] {
+        let mut mac = self.hmac();
+        mac.update(&Self::message(client_id, addr, priority, timestamp));
+        mac.finalize().into_bytes()[..MAC_SIZE].try_into().unwrap()
+    }
+
+    fn hmac(&self) -> Hmac<Sha256> {
+        Hmac::<Sha256>::new_from_slice(&self.key).unwrap()
+    }
+
+    fn message(client_id: UnetId, addr: SocketAddr, priority: u8, timestamp: u64) -> Vec<u8> {
+        let mut message = vec![];
+        message.extend_from_slice(&timestamp.to_be_bytes());
+        message.extend_from_slice(&client_id.0.to_be_bytes());
+        match addr.ip().to_canonical() {
+            IpAddr::V4(ip) => message.extend_from_slice(&ip.octets()),
+            IpAddr::V6(ip) => message.extend_from_slice(&ip.octets()),
+        }
+        message.extend_from_slice(&addr.port().to_be_bytes());
+        message.push(priority);
+        message
+    }
+}
+
+impl Default for CookieKey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Keeps the key out of debug output
+impl fmt::Debug for CookieKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CookieKey").finish_non_exhaustive()
+    }
+}
+
+/// Cookies that already got a client in, so a captured `ChallengeResponse` can't be replayed
+/// while its cookie is still fresh. They're forgotten once they'd have expired anyway.
+#[derive(Clone, Debug, Default)]
+pub struct UsedCookies {
+    macs: HashSet<[u8; MAC_SIZE]>,
+    by_age: VecDeque<Cookie>, // In the order they were used, which is close to oldest first
+}
+
+impl UsedCookies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// False if `cookie` was used before.
+    pub fn insert(&mut self, cookie: Cookie) -> bool {
+        if !self.macs.insert(cookie.mac) {
+            return false;
+        }
+        self.by_age.push_back(cookie);
+        true
+    }
+
+    pub fn remove_expired(&mut self, now: Tick, lifetime: Tick) {
+        let now = now.value as u64;
+        while let Some(cookie) = self.by_age.front() {
+            if now.saturating_sub(cookie.timestamp) <= lifetime.value as u64 {
+                break;
+            }
+            self.macs.remove(&cookie.mac);
+            self.by_age.pop_front();
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_age.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cookie::{Cookie, CookieKey, UsedCookies};
+    use crate::packet::UnetId;
+    use crate::tick::Tick;
+    use std::net::SocketAddr;
+
+    const LIFETIME: Tick = Tick { value: 40.0 };
+
+    fn addr() -> SocketAddr {
+        "10.0.0.1:20000".parse().unwrap()
+    }
+
+    #[test]
+    fn valid() {
+        let key = CookieKey::new();
+        let cookie = key.issue(UnetId(1), addr(), 0, Tick { value: 10.0 });
+        assert!(key.verify(cookie, UnetId(1), addr(), 0, Tick { value: 50.0 }, LIFETIME));
+    }
+
+    #[test]
+    fn expired() {
+        let key = CookieKey::new();
+        let cookie = key.issue(UnetId(1), addr(), 0, Tick { value: 10.0 });
+        assert!(!key.verify(cookie, UnetId(1), addr(), 0, Tick { value: 51.0 }, LIFETIME));
+    }
+
+    #[test]
+    fn bound_to_client() {
+        let key = CookieKey::new();
+        let cookie = key.issue(UnetId(1), addr(), 0, Tick { value: 0.0 });
+        let now = Tick { value: 0.0 };
+        assert!(!key.verify(cookie, UnetId(2), addr(), 0, now, LIFETIME));
+        let other: SocketAddr = "10.0.0.1:20001".parse().unwrap();
+        assert!(!key.verify(cookie, UnetId(1), other, 0, now, LIFETIME));
+        // Can't claim a higher priority than it asked for
+        assert!(!key.verify(cookie, UnetId(1), addr(), 1, now, LIFETIME));
+    }
+
+    #[test]
+    fn forged() {
+        let key = CookieKey::new();
+        let now = Tick { value: 0.0 };
+        let mut cookie = key.issue(UnetId(1), addr(), 0, now);
+        cookie.mac[0] ^= 1;
+        assert!(!key.verify(cookie, UnetId(1), addr(), 0, now, LIFETIME));
+
+        // Another server's cookie
+        let cookie = CookieKey::new().issue(UnetId(1), addr(), 0, now);
+        assert!(!key.verify(cookie, UnetId(1), addr(), 0, now, LIFETIME));
+    }
+
+    #[test]
+    fn round_trip() {
+        let cookie = CookieKey::new().issue(UnetId(1), addr(), 0, Tick { value: 3.0 });
+        assert_eq!(Cookie::from_bytes(&cookie.as_bytes()), cookie);
+    }
+
+    #[test]
+    fn used_once() {
+        let key = CookieKey::new();
+        let mut used = UsedCookies::new();
+        let cookie = key.issue(UnetId(1), addr(), 0, Tick { value: 10.0 });
+        assert!(used.insert(cookie));
+        assert!(!used.insert(cookie));
+
+        // Kept for as long as the cookie would verify
+        used.remove_expired(Tick { value: 50.0 }, LIFETIME);
+        assert!(!used.insert(cookie));
+        used.remove_expired(Tick { value: 51.0 }, LIFETIME);
+        assert!(used.is_empty());
+    }
+}
@@ -1,7 +1,6 @@
 use crate::packet::Packet;
 use crate::server::connection::ConnectionIdentifier;
 use colored::Colorize;
-use std::net::ToSocketAddrs;
 
 pub struct Color {
     pub r: u8,
@@ -115,9 +114,20 @@ pub fn client_disconnect_dbg(connection_identifier: ConnectionIdentifier, index:
     )
 }
 
+pub fn client_migrate_dbg(from: ConnectionIdentifier, to: ConnectionIdentifier, index: usize) {
+    println!(
+        "[{}] [{}] [{}] -> [{}] on connection slot {}",
+        "migrated".truecolor(255, 215, 0),
+        format!("{:16x}", from.id.0).truecolor(BLUE.r, BLUE.g, BLUE.b),
+        from.addr.to_string().truecolor(255, 215, 0),
+        to.addr.to_string().truecolor(255, 215, 0),
+        index.to_string().truecolor(255, 215, 0),
+    )
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::debug::{client_connect_dbg, client_disconnect_dbg, recv_dbg};
+    use crate::debug::{client_connect_dbg, client_disconnect_dbg, client_migrate_dbg, recv_dbg};
     use crate::packet::keep_alive::KeepAlive;
     use crate::packet::{Packet, UnetId};
     use crate::server::connection::ConnectionIdentifier;
@@ -143,5 +153,8 @@ mod tests {
             ConnectionIdentifier::new(UnetId(0xdeadbeef), "0.0.0.0:0".parse().unwrap());
         client_connect_dbg(connection_identifier, 0);
         client_disconnect_dbg(connection_identifier, 0);
+        let migrated =
+            ConnectionIdentifier::new(UnetId(0xdeadbeef), "[::1]:10010".parse().unwrap());
+        client_migrate_dbg(connection_identifier, migrated, 0);
     }
 }
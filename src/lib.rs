@@ -3,20 +3,29 @@ use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
 use std::time::Duration;
 
 pub mod client;
+pub mod clock;
 pub mod config;
+pub mod congestion;
+pub mod cookie;
 pub mod debug;
+pub mod mtu;
 pub mod network;
 pub mod packet;
+pub mod replay_window;
 pub mod rolling_average;
 pub mod server;
+pub mod stats;
 pub mod tick;
 pub mod token;
+pub mod token_bucket;
+pub mod user_data;
 
 pub const DEFAULT_SERVER_ADDR: SocketAddr =
     SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 10010));
 
 pub const MAX_CONNECTIONS: usize = 256;
-pub const BUF_SIZE: usize = 640;
+pub const BUF_SIZE: usize = 640; // Packet size that's assumed to fit on any path
+pub const MAX_PACKET_SIZE: usize = 1472; // Ethernet MTU minus IPv4 and UDP headers
 
 pub const DEFAULT_TPS: f32 = 20.0;
 pub const DEFAULT_CLIENT_CONNECTION_TIMEOUT: Tick =
@@ -25,3 +34,10 @@ pub const DEFAULT_SERVER_NOT_RESPONDING_TIMEOUT: Tick =
     Tick::from_duration(Duration::from_secs(4), DEFAULT_TPS);
 pub const DEFAULT_KEEP_ALIVE_FREQUENCY: Tick =
     Tick::from_duration(Duration::from_millis(200), DEFAULT_TPS);
+pub const DEFAULT_PING_FREQUENCY: Tick =
+    Tick::from_duration(Duration::from_millis(500), DEFAULT_TPS);
+pub const DEFAULT_HANDSHAKE_RESEND_INTERVAL: Tick =
+    Tick::from_duration(Duration::from_millis(100), DEFAULT_TPS);
+pub const DEFAULT_CONNECT_TIMEOUT: Tick = Tick::from_duration(Duration::from_secs(5), DEFAULT_TPS);
+pub const DEFAULT_HANDSHAKE_TIMEOUT: Tick =
+    Tick::from_duration(Duration::from_secs(2), DEFAULT_TPS);
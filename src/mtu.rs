@@ -0,0 +1,181 @@
+use crate::tick::Tick;
+use crate::{BUF_SIZE, MAX_PACKET_SIZE};
+use std::time::Duration;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MtuConfig {
+    pub max_mtu: usize, // Largest packet size worth probing for, capped at MAX_PACKET_SIZE
+    pub step: usize,    // How much bigger each probe is than the last acknowledged size
+    pub attempts: u32,  // Unacknowledged probes of one size before giving up on it
+    pub probe_interval: Duration, // How long to wait for an ack before probing again
+}
+
+impl MtuConfig {
+    pub fn new() -> Self {
+        Self {
+            max_mtu: MAX_PACKET_SIZE,
+            step: 64,
+            attempts: 3,
+            probe_interval: Duration::from_millis(200),
+        }
+    }
+}
+
+impl Default for MtuConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Finds the largest packet that makes it to the other side by sending probes of increasing
+/// size. Starts from `BUF_SIZE`, which is assumed to always fit, and stops at the first size that
+/// isn't acknowledged after `attempts` tries.
+#[derive(Clone, Debug)]
+pub struct MtuDiscovery {
+    pub config: MtuConfig,
+    mtu: usize,                   // Largest acknowledged size so far
+    probing: Option<usize>,       // Size of the probe we're waiting on
+    attempts: u32,                // Probes sent of that size
+    ticks_since_last_probe: Tick, // Needed for tracking when to send the next probe
+    probe_interval: Tick,
+    done: bool,
+}
+
+impl MtuDiscovery {
+    pub fn new(config: MtuConfig, tps: f32) -> Self {
+        let probe_interval = Tick::from_duration(config.probe_interval, tps);
+        Self {
+            config,
+            mtu: BUF_SIZE,
+            probing: None,
+            attempts: 0,
+            ticks_since_last_probe: probe_interval,
+            probe_interval,
+            done: false,
+        }
+    }
+
+    /// Discovered MTU, `BUF_SIZE` until something bigger has been acknowledged.
+    pub fn mtu(&self) -> usize {
+        self.mtu
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Returns the size of the next probe to send, if it's time to send one.
+    pub fn next_probe(&mut self) -> Option<u16> {
+        if self.done || self.ticks_since_last_probe < self.probe_interval {
+            return None;
+        }
+
+        let max_mtu = self.config.max_mtu.min(MAX_PACKET_SIZE);
+        let size = (self.mtu + self.config.step.max(1)).min(max_mtu);
+        if size <= self.mtu {
+            self.done = true;
+            return None;
+        }
+
+        if self.probing == Some(size) {
+            self.attempts += 1;
+        } else {
+            self.probing = Some(size);
+            self.attempts = 1;
+        }
+
+        if self.attempts > self.config.attempts {
+            // Too big for this path, stick with what we have
+            self.done = true;
+            self.probing = None;
+            return None;
+        }
+
+        self.ticks_since_last_probe.value = 0.0;
+        Some(size as u16)
+    }
+
+    pub fn ack_received(&mut self, size: u16) {
+        let size = size as usize;
+        if self.done || Some(size) != self.probing {
+            // Late or duplicate ack
+            return;
+        }
+
+        self.mtu = size;
+        self.probing = None;
+        self.attempts = 0;
+        // Don't wait for the interval, try the next size right away
+        self.ticks_since_last_probe = self.probe_interval;
+    }
+
+    pub fn tick(&mut self) {
+        self.ticks_since_last_probe.value += 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mtu::{MtuConfig, MtuDiscovery};
+    use crate::BUF_SIZE;
+
+    fn discover(path_mtu: usize, config: MtuConfig) -> MtuDiscovery {
+        let mut discovery = MtuDiscovery::new(config, 20.0);
+        for _ in 0..1000 {
+            if let Some(size) = discovery.next_probe() {
+                if size as usize <= path_mtu {
+                    discovery.ack_received(size);
+                }
+            }
+            discovery.tick();
+        }
+        discovery
+    }
+
+    #[test]
+    fn starts_at_conservative_default() {
+        let discovery = MtuDiscovery::new(MtuConfig::new(), 20.0);
+        assert_eq!(discovery.mtu(), BUF_SIZE);
+    }
+
+    #[test]
+    fn finds_largest_acknowledged_size() {
+        let discovery = discover(1300, MtuConfig::new());
+        assert!(discovery.is_done());
+        assert_eq!(discovery.mtu(), 1280);
+    }
+
+    #[test]
+    fn stops_at_max_mtu() {
+        let mut config = MtuConfig::new();
+        config.max_mtu = 1000;
+        let discovery = discover(usize::MAX, config);
+        assert!(discovery.is_done());
+        assert_eq!(discovery.mtu(), 1000);
+    }
+
+    #[test]
+    fn nothing_acknowledged() {
+        let discovery = discover(0, MtuConfig::new());
+        assert!(discovery.is_done());
+        assert_eq!(discovery.mtu(), BUF_SIZE);
+    }
+
+    #[test]
+    fn gives_up_after_attempts() {
+        let mut config = MtuConfig::new();
+        config.attempts = 2;
+        let mut discovery = MtuDiscovery::new(config, 20.0);
+        assert_eq!(discovery.next_probe(), Some(704));
+        assert_eq!(discovery.next_probe(), None); // Still waiting for the ack
+        for _ in 0..4 {
+            discovery.tick();
+        }
+        assert_eq!(discovery.next_probe(), Some(704));
+        for _ in 0..4 {
+            discovery.tick();
+        }
+        assert_eq!(discovery.next_probe(), None);
+        assert!(discovery.is_done());
+    }
+}
@@ -1,68 +1,200 @@
+pub mod link_conditioner;
+pub mod udp;
+
+use crate::clock::{Clock, SystemClock};
+use crate::network::link_conditioner::{LinkConditioner, LinkConditionerConfig};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
 use std::io;
 use std::net::{SocketAddr, UdpSocket};
-use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Anything that can move datagrams between unet endpoints.
+///
+/// `recv_from` must never block, when there's nothing to read it should return an
+/// error of kind `WouldBlock`.
+pub trait Transport: Debug + Send {
+    fn send_to(&self, buf: &[u8], to: SocketAddr) -> io::Result<usize>;
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)>;
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+}
+
+impl Transport for UdpSocket {
+    fn send_to(&self, buf: &[u8], to: SocketAddr) -> io::Result<usize> {
+        UdpSocket::send_to(self, buf, to)
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        UdpSocket::recv_from(self, buf)
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        UdpSocket::local_addr(self)
+    }
+}
 
 #[derive(Debug)]
-pub struct VirtualNetwork {
-    pub tx: Sender<Vec<u8>>,
-    pub rx: Receiver<Vec<u8>>,
+struct InFlight {
+    deliver_at: Instant,
+    from: SocketAddr,
+    bytes: Vec<u8>,
 }
 
 #[derive(Debug)]
-pub enum Network {
-    Real(UdpSocket),
-    Virtual(VirtualNetwork),
+struct VirtualHubState {
+    endpoints: HashMap<SocketAddr, Sender<InFlight>>,
+    clock: Box<dyn Clock>,
 }
 
-impl Network {
-    pub fn send_to(&self, buf: &[u8], to: SocketAddr) -> io::Result<usize> {
-        match self {
-            Network::Real(socket) => socket.send_to(buf, to),
-            Network::Virtual(virtual_network) => {
-                virtual_network.tx.send(buf.to_vec()).unwrap();
-                Ok(buf.len())
-            }
+/// A virtual switch connecting any number of `VirtualNetwork` endpoints, each with its own
+/// address. Clones share the same hub.
+#[derive(Clone, Debug)]
+pub struct VirtualHub {
+    state: Arc<Mutex<VirtualHubState>>,
+}
+
+impl VirtualHub {
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock)
+    }
+
+    /// Delivery times of conditioned endpoints are measured with `clock`, which is usually
+    /// a `ManualClock` shared with the server and clients.
+    pub fn with_clock(clock: impl Clock + 'static) -> Self {
+        let state = VirtualHubState {
+            endpoints: HashMap::new(),
+            clock: Box::new(clock),
+        };
+
+        Self {
+            state: Arc::new(Mutex::new(state)),
         }
     }
 
-    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
-        match self {
-            Network::Real(socket) => socket.send(buf),
-            Network::Virtual(virtual_network) => {
-                virtual_network.tx.send(buf.to_vec()).unwrap();
-                Ok(buf.len())
-            }
+    pub fn endpoint(&self, addr: SocketAddr) -> io::Result<VirtualNetwork> {
+        self.attach(addr, None)
+    }
+
+    /// Everything sent from this endpoint goes through a link conditioner seeded with `seed`.
+    pub fn conditioned_endpoint(
+        &self,
+        addr: SocketAddr,
+        config: LinkConditionerConfig,
+        seed: u64,
+    ) -> io::Result<VirtualNetwork> {
+        self.attach(addr, Some(LinkConditioner::new(config, seed)))
+    }
+
+    fn attach(
+        &self,
+        addr: SocketAddr,
+        conditioner: Option<LinkConditioner>,
+    ) -> io::Result<VirtualNetwork> {
+        let mut state = self.state.lock().unwrap();
+        if state.endpoints.contains_key(&addr) {
+            return Err(io::ErrorKind::AddrInUse.into());
+        }
+
+        let (tx, rx) = channel();
+        state.endpoints.insert(addr, tx);
+
+        Ok(VirtualNetwork {
+            addr,
+            hub: self.clone(),
+            rx,
+            conditioner: conditioner.map(RefCell::new),
+            in_flight: RefCell::new(BTreeMap::new()),
+            received: Cell::new(0),
+        })
+    }
+
+    fn now(&self) -> Instant {
+        self.state.lock().unwrap().clock.now()
+    }
+}
+
+impl Default for VirtualHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub struct VirtualNetwork {
+    addr: SocketAddr,
+    hub: VirtualHub,
+    rx: Receiver<InFlight>,
+    conditioner: Option<RefCell<LinkConditioner>>,
+    in_flight: RefCell<BTreeMap<(Instant, u64), InFlight>>, // Ordered by delivery time, then arrival
+    received: Cell<u64>,
+}
+
+impl VirtualNetwork {
+    fn next_delivered(&self) -> Option<InFlight> {
+        let mut in_flight = self.in_flight.borrow_mut();
+        while let Ok(packet) = self.rx.try_recv() {
+            let received = self.received.get();
+            self.received.set(received + 1);
+            in_flight.insert((packet.deliver_at, received), packet);
         }
+
+        let now = self.hub.now();
+        let entry = in_flight.first_entry()?;
+        if entry.key().0 > now {
+            return None;
+        }
+
+        Some(entry.remove())
     }
+}
 
-    pub fn recv_from(&self, mut buf: &mut [u8]) -> Option<(usize, SocketAddr)> {
-        match self {
-            Network::Real(socket) => {
-                let (n, from) = match socket.recv_from(buf) {
-                    Ok((n, from)) => (n, from),
-                    Err(e) => return None,
+impl Transport for VirtualNetwork {
+    fn send_to(&self, buf: &[u8], to: SocketAddr) -> io::Result<usize> {
+        let state = self.hub.state.lock().unwrap();
+        let now = state.clock.now();
+        let deliveries = match &self.conditioner {
+            Some(conditioner) => conditioner.borrow_mut().condition(buf.len(), now),
+            None => vec![now],
+        };
+
+        // Just like UDP, sending to an address nobody is listening on silently goes nowhere
+        if let Some(tx) = state.endpoints.get(&to) {
+            for deliver_at in deliveries {
+                let packet = InFlight {
+                    deliver_at,
+                    from: self.addr,
+                    bytes: buf.to_vec(),
                 };
-                Some((n, from))
-            }
-            Network::Virtual(virtual_network) => {
-                let output = &virtual_network.rx.try_recv().unwrap_or_else(|e| match e {
-                    TryRecvError::Empty => {
-                        vec![]
-                    }
-                    TryRecvError::Disconnected => {
-                        unreachable!()
-                    }
-                });
-
-                if output.is_empty() {
-                    return None;
-                }
-
-                buf = &mut buf[..output.len()];
-                buf.clone_from_slice(output);
-
-                Some((output.len(), "0.0.0.0:0".parse().unwrap()))
+                let _ = tx.send(packet);
             }
         }
+
+        Ok(buf.len())
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let Some(packet) = self.next_delivered() else {
+            return Err(io::ErrorKind::WouldBlock.into());
+        };
+
+        let n = packet.bytes.len().min(buf.len());
+        buf[..n].copy_from_slice(&packet.bytes[..n]);
+
+        Ok((n, packet.from))
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.addr)
+    }
+}
+
+impl Drop for VirtualNetwork {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.hub.state.lock() {
+            state.endpoints.remove(&self.addr);
+        }
     }
 }
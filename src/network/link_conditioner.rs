@@ -0,0 +1,195 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+/// Simulated network conditions for one direction of a virtual link.
+/// Percentages are in the range `0.0..=100.0`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LinkConditionerConfig {
+    pub latency: Duration,
+    pub jitter: Duration, // Up to this much extra latency, picked per packet
+    pub loss: f32,
+    pub duplicate: f32,
+    pub reorder: f32,
+    pub reorder_delay: Duration, // Up to this much extra latency for reordered packets
+    pub bandwidth: Option<u64>,  // Bytes per second, None for unlimited
+    pub mtu: Option<usize>,      // Bigger packets are dropped, None for unlimited
+}
+
+impl LinkConditionerConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Debug)]
+pub struct LinkConditioner {
+    pub config: LinkConditionerConfig,
+    rng: StdRng,
+    link_free_at: Option<Instant>,
+}
+
+impl LinkConditioner {
+    pub fn new(config: LinkConditionerConfig, seed: u64) -> Self {
+        Self {
+            config,
+            rng: StdRng::seed_from_u64(seed),
+            link_free_at: None,
+        }
+    }
+
+    /// Returns when each copy of a packet of `len` bytes sent at `now` should be delivered.
+    /// An empty result means the packet was lost.
+    pub fn condition(&mut self, len: usize, now: Instant) -> Vec<Instant> {
+        if self.config.mtu.is_some_and(|mtu| len > mtu) {
+            return vec![];
+        }
+
+        if self.roll(self.config.loss) {
+            return vec![];
+        }
+
+        let mut departure = now;
+        if let Some(bandwidth) = self.config.bandwidth {
+            let start = self.link_free_at.map_or(now, |free_at| free_at.max(now));
+            let transmission = Duration::from_secs_f64(len as f64 / bandwidth.max(1) as f64);
+            departure = start + transmission;
+            self.link_free_at = Some(departure);
+        }
+
+        let mut copies = 1;
+        if self.roll(self.config.duplicate) {
+            copies += 1;
+        }
+
+        let mut deliveries = vec![];
+        for _ in 0..copies {
+            let mut deliver_at =
+                departure + self.config.latency + self.random_up_to(self.config.jitter);
+            if self.roll(self.config.reorder) {
+                deliver_at += self.random_up_to(self.config.reorder_delay);
+            }
+            deliveries.push(deliver_at);
+        }
+
+        deliveries
+    }
+
+    fn roll(&mut self, percentage: f32) -> bool {
+        percentage > 0.0 && self.rng.gen_range(0.0..100.0) < percentage
+    }
+
+    fn random_up_to(&mut self, max: Duration) -> Duration {
+        if max.is_zero() {
+            return Duration::ZERO;
+        }
+
+        Duration::from_nanos(self.rng.gen_range(0..=max.as_nanos() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::network::link_conditioner::{LinkConditioner, LinkConditionerConfig};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn perfect_link() {
+        let mut conditioner = LinkConditioner::new(LinkConditionerConfig::new(), 0);
+        let now = Instant::now();
+        assert_eq!(conditioner.condition(100, now), vec![now]);
+    }
+
+    #[test]
+    fn latency() {
+        let mut config = LinkConditionerConfig::new();
+        config.latency = Duration::from_millis(100);
+        let mut conditioner = LinkConditioner::new(config, 0);
+        let now = Instant::now();
+        assert_eq!(
+            conditioner.condition(100, now),
+            vec![now + Duration::from_millis(100)]
+        );
+    }
+
+    #[test]
+    fn jitter_is_bounded() {
+        let mut config = LinkConditionerConfig::new();
+        config.latency = Duration::from_millis(100);
+        config.jitter = Duration::from_millis(20);
+        let mut conditioner = LinkConditioner::new(config, 7);
+        let now = Instant::now();
+        for _ in 0..1000 {
+            let deliver_at = conditioner.condition(100, now)[0];
+            assert!(deliver_at >= now + Duration::from_millis(100));
+            assert!(deliver_at <= now + Duration::from_millis(120));
+        }
+    }
+
+    #[test]
+    fn total_loss() {
+        let mut config = LinkConditionerConfig::new();
+        config.loss = 100.0;
+        let mut conditioner = LinkConditioner::new(config, 0);
+        assert!(conditioner.condition(100, Instant::now()).is_empty());
+    }
+
+    #[test]
+    fn partial_loss() {
+        let mut config = LinkConditionerConfig::new();
+        config.loss = 25.0;
+        let mut conditioner = LinkConditioner::new(config, 42);
+        let now = Instant::now();
+        let delivered = (0..10000)
+            .filter(|_| !conditioner.condition(100, now).is_empty())
+            .count();
+        assert!((7000..8000).contains(&delivered), "{delivered}");
+    }
+
+    #[test]
+    fn mtu() {
+        let mut config = LinkConditionerConfig::new();
+        config.mtu = Some(1000);
+        let mut conditioner = LinkConditioner::new(config, 0);
+        let now = Instant::now();
+        assert_eq!(conditioner.condition(1000, now), vec![now]);
+        assert!(conditioner.condition(1001, now).is_empty());
+    }
+
+    #[test]
+    fn duplicate() {
+        let mut config = LinkConditionerConfig::new();
+        config.duplicate = 100.0;
+        let mut conditioner = LinkConditioner::new(config, 0);
+        assert_eq!(conditioner.condition(100, Instant::now()).len(), 2);
+    }
+
+    #[test]
+    fn bandwidth() {
+        let mut config = LinkConditionerConfig::new();
+        config.bandwidth = Some(1000);
+        let mut conditioner = LinkConditioner::new(config, 0);
+        let now = Instant::now();
+        assert_eq!(
+            conditioner.condition(100, now),
+            vec![now + Duration::from_millis(100)]
+        );
+        assert_eq!(
+            conditioner.condition(100, now),
+            vec![now + Duration::from_millis(200)]
+        );
+    }
+
+    #[test]
+    fn same_seed_same_result() {
+        let mut config = LinkConditionerConfig::new();
+        config.loss = 50.0;
+        config.jitter = Duration::from_millis(50);
+        let mut a = LinkConditioner::new(config, 1234);
+        let mut b = LinkConditioner::new(config, 1234);
+        let now = Instant::now();
+        for _ in 0..100 {
+            assert_eq!(a.condition(100, now), b.condition(100, now));
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use crate::network::Transport;
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io;
+use std::net::{SocketAddr, SocketAddrV6, UdpSocket};
+
+/// One or more non-blocking UDP sockets acting as a single transport.
+///
+/// Replies go out of the socket matching the family of the destination. A dual-stack IPv6
+/// socket also serves IPv4 peers, those are always reported as plain IPv4 addresses and
+/// mapped back to `::ffff:a.b.c.d` when sending.
+#[derive(Debug)]
+pub struct UdpTransport {
+    sockets: Vec<UdpSocket>,
+    dual_stack: bool,
+}
+
+impl UdpTransport {
+    /// Binds a socket on every address in `addrs`. With `dual_stack` every IPv6 socket is
+    /// bound with `IPV6_V6ONLY` disabled.
+    pub fn bind(addrs: &[SocketAddr], dual_stack: bool) -> io::Result<Self> {
+        if addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "UdpTransport needs at least one address to bind",
+            ));
+        }
+
+        let mut sockets = vec![];
+        for addr in addrs {
+            let socket = Socket::new(Domain::for_address(*addr), Type::DGRAM, Some(Protocol::UDP))?;
+            if addr.is_ipv6() {
+                socket.set_only_v6(!dual_stack)?;
+            }
+            socket.bind(&(*addr).into())?;
+            socket.set_nonblocking(true)?;
+            sockets.push(UdpSocket::from(socket));
+        }
+
+        Ok(Self {
+            sockets,
+            dual_stack,
+        })
+    }
+
+    /// Every address this transport is listening on.
+    pub fn local_addrs(&self) -> io::Result<Vec<SocketAddr>> {
+        self.sockets.iter().map(UdpSocket::local_addr).collect()
+    }
+
+    fn socket_for(&self, to: SocketAddr) -> io::Result<(&UdpSocket, SocketAddr)> {
+        for socket in &self.sockets {
+            if socket.local_addr()?.is_ipv4() == to.is_ipv4() {
+                return Ok((socket, to));
+            }
+        }
+
+        if let (true, SocketAddr::V4(v4)) = (self.dual_stack, to) {
+            if let Some(socket) = self.sockets.first() {
+                let mapped = SocketAddrV6::new(v4.ip().to_ipv6_mapped(), v4.port(), 0, 0);
+                return Ok((socket, SocketAddr::V6(mapped)));
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::AddrNotAvailable,
+            format!("No socket can reach {to}"),
+        ))
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send_to(&self, buf: &[u8], to: SocketAddr) -> io::Result<usize> {
+        let (socket, to) = self.socket_for(canonical_addr(to))?;
+        socket.send_to(buf, to)
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        for socket in &self.sockets {
+            match socket.recv_from(buf) {
+                Ok((n, from)) => return Ok((n, canonical_addr(from))),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(io::ErrorKind::WouldBlock.into())
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.sockets[0].local_addr()
+    }
+}
+
+/// Turns IPv4-mapped IPv6 addresses (`[::ffff:127.0.0.1]:10010`) into plain IPv4 ones
+/// (`127.0.0.1:10010`), so a peer has one address no matter which socket it came through.
+pub fn canonical_addr(addr: SocketAddr) -> SocketAddr {
+    SocketAddr::new(addr.ip().to_canonical(), addr.port())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::network::udp::{canonical_addr, UdpTransport};
+    use crate::network::Transport;
+    use std::net::SocketAddr;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    #[test]
+    fn canonical_addr_unmaps_ipv4() {
+        let mapped: SocketAddr = "[::ffff:127.0.0.1]:10010".parse().unwrap();
+        let plain: SocketAddr = "127.0.0.1:10010".parse().unwrap();
+        assert_eq!(canonical_addr(mapped), plain);
+        assert_eq!(canonical_addr(plain), plain);
+    }
+
+    #[test]
+    fn canonical_addr_keeps_ipv6() {
+        let addr: SocketAddr = "[::1]:10010".parse().unwrap();
+        assert_eq!(canonical_addr(addr), addr);
+    }
+
+    fn recv(transport: &UdpTransport, buf: &mut [u8]) -> (usize, SocketAddr) {
+        for _ in 0..100 {
+            if let Ok(received) = transport.recv_from(buf) {
+                return received;
+            }
+            sleep(Duration::from_millis(10));
+        }
+        panic!("Nothing received");
+    }
+
+    #[test]
+    fn dual_stack() {
+        let server = UdpTransport::bind(&["[::]:0".parse().unwrap()], true).unwrap();
+        let port = server.local_addr().unwrap().port();
+        let client = UdpTransport::bind(&["127.0.0.1:0".parse().unwrap()], false).unwrap();
+
+        let server_addr = SocketAddr::new("127.0.0.1".parse().unwrap(), port);
+        client.send_to(b"ping", server_addr).unwrap();
+
+        let mut buf = [0; 16];
+        let (n, from) = recv(&server, &mut buf);
+        assert_eq!(&buf[..n], b"ping");
+        assert_eq!(from, client.local_addr().unwrap());
+
+        server.send_to(b"pong", from).unwrap();
+        let (n, _) = recv(&client, &mut buf);
+        assert_eq!(&buf[..n], b"pong");
+    }
+}
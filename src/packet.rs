@@ -1,17 +1,33 @@
+pub mod challenge_request;
 pub mod challenge_response;
 pub mod connection_request;
 pub mod data;
 pub mod disconnect;
 pub mod keep_alive;
+pub mod mtu_probe;
+pub mod mtu_probe_ack;
+pub mod path_challenge;
+pub mod path_response;
+pub mod ping;
+pub mod pong;
+pub mod queued;
 
+use crate::packet::challenge_request::ChallengeRequest;
 use crate::packet::challenge_response::ChallengeResponse;
 use crate::packet::connection_request::ConnectionRequest;
 use crate::packet::data::Data;
 use crate::packet::disconnect::Disconnect;
 use crate::packet::keep_alive::KeepAlive;
+use crate::packet::mtu_probe::MtuProbe;
+use crate::packet::mtu_probe_ack::MtuProbeAck;
+use crate::packet::path_challenge::PathChallenge;
+use crate::packet::path_response::PathResponse;
+use crate::packet::ping::Ping;
+use crate::packet::pong::Pong;
+use crate::packet::queued::Queued;
 use rand::random;
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 #[repr(C)]
 pub struct UnetId(pub u64);
 
@@ -37,19 +53,33 @@ pub enum PacketKind {
     KeepAlive = 3,
     Data = 4,
     Disconnect = 5,
-    Unimplemented,
+    Ping = 6,
+    Pong = 7,
+    MtuProbe = 8,
+    MtuProbeAck = 9,
+    PathChallenge = 10,
+    PathResponse = 11,
+    Queued = 12,
 }
 impl PacketKind {
-    pub fn from_byte(byte: u8) -> Self {
-        match byte {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        let kind = match byte {
             0 => PacketKind::ConnectionRequest,
             1 => PacketKind::ChallengeRequest,
             2 => PacketKind::ChallengeResponse,
             3 => PacketKind::KeepAlive,
             4 => PacketKind::Data,
             5 => PacketKind::Disconnect,
-            _ => PacketKind::Unimplemented,
-        }
+            6 => PacketKind::Ping,
+            7 => PacketKind::Pong,
+            8 => PacketKind::MtuProbe,
+            9 => PacketKind::MtuProbeAck,
+            10 => PacketKind::PathChallenge,
+            11 => PacketKind::PathResponse,
+            12 => PacketKind::Queued,
+            _ => return None,
+        };
+        Some(kind)
     }
 
     pub fn as_byte(&self) -> u8 {
@@ -60,23 +90,55 @@ impl PacketKind {
             PacketKind::KeepAlive => 3,
             PacketKind::Data => 4,
             PacketKind::Disconnect => 5,
-            PacketKind::Unimplemented => {
-                panic!("Tried calling as_byte() on PacketKind::Unimplemented")
-            }
+            PacketKind::Ping => 6,
+            PacketKind::Pong => 7,
+            PacketKind::MtuProbe => 8,
+            PacketKind::MtuProbeAck => 9,
+            PacketKind::PathChallenge => 10,
+            PacketKind::PathResponse => 11,
+            PacketKind::Queued => 12,
+        }
+    }
+
+    /// Shortest a packet of this kind can be on the wire, not counting the kind byte.
+    pub fn min_size(&self) -> usize {
+        match self {
+            // Not padded, answering it would amplify
+            PacketKind::ConnectionRequest => ConnectionRequest::SIZE,
+            PacketKind::ChallengeRequest => ChallengeRequest::SIZE,
+            PacketKind::ChallengeResponse => ChallengeResponse::MIN_SIZE,
+            PacketKind::KeepAlive => KeepAlive::SIZE,
+            PacketKind::Data => Data::SIZE,
+            PacketKind::Disconnect => Disconnect::SIZE,
+            PacketKind::Ping => Ping::SIZE,
+            PacketKind::Pong => Pong::SIZE,
+            PacketKind::MtuProbe => MtuProbe::MIN_SIZE - 1,
+            PacketKind::MtuProbeAck => MtuProbeAck::SIZE,
+            PacketKind::PathChallenge => PathChallenge::SIZE,
+            PacketKind::PathResponse => PathResponse::SIZE,
+            PacketKind::Queued => Queued::SIZE,
         }
     }
 }
 
+// ChallengeResponse carries its UserData inline, boxing it would cost us Copy
+#[allow(clippy::large_enum_variant)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 #[repr(usize)]
 pub enum Packet {
     ConnectionRequest(ConnectionRequest),
-    ChallengeRequest,
+    ChallengeRequest(ChallengeRequest),
     ChallengeResponse(ChallengeResponse),
     KeepAlive(KeepAlive),
     Data(Data),
     Disconnect(Disconnect),
-    Unimplemented,
+    Ping(Ping),
+    Pong(Pong),
+    MtuProbe(MtuProbe),
+    MtuProbeAck(MtuProbeAck),
+    PathChallenge(PathChallenge),
+    PathResponse(PathResponse),
+    Queued(Queued),
 }
 
 impl Packet {
@@ -86,15 +148,21 @@ impl Packet {
             return None;
         }
 
-        let packet_kind = PacketKind::from_byte(bytes[0]);
+        let packet_kind = PacketKind::from_byte(bytes[0])?;
+        if bytes.len() - 1 < packet_kind.min_size() {
+            return None;
+        }
+
         let packet = match packet_kind {
             PacketKind::ConnectionRequest => {
                 let connection_request = ConnectionRequest::from_bytes(&bytes[1..]);
                 Packet::ConnectionRequest(connection_request)
             }
-            PacketKind::ChallengeRequest => Packet::ChallengeRequest,
+            PacketKind::ChallengeRequest => {
+                Packet::ChallengeRequest(ChallengeRequest::from_bytes(&bytes[1..]))
+            }
             PacketKind::ChallengeResponse => {
-                Packet::ChallengeResponse(ChallengeResponse::from_bytes(&bytes[1..]))
+                Packet::ChallengeResponse(ChallengeResponse::from_bytes(&bytes[1..])?)
             }
             PacketKind::KeepAlive => {
                 let keep_alive = KeepAlive::from_bytes(&bytes[1..]);
@@ -105,10 +173,18 @@ impl Packet {
                 Packet::Data(data)
             }
             PacketKind::Disconnect => {
-                let disconnect = Disconnect::from_bytes(&bytes[1..]);
+                let disconnect = Disconnect::from_bytes(&bytes[1..])?;
                 Packet::Disconnect(disconnect)
             }
-            _ => Packet::Unimplemented,
+            PacketKind::Ping => Packet::Ping(Ping::from_bytes(&bytes[1..])),
+            PacketKind::Pong => Packet::Pong(Pong::from_bytes(&bytes[1..])),
+            PacketKind::MtuProbe => Packet::MtuProbe(MtuProbe::from_bytes(&bytes[1..])),
+            PacketKind::MtuProbeAck => Packet::MtuProbeAck(MtuProbeAck::from_bytes(&bytes[1..])),
+            PacketKind::PathChallenge => {
+                Packet::PathChallenge(PathChallenge::from_bytes(&bytes[1..]))
+            }
+            PacketKind::PathResponse => Packet::PathResponse(PathResponse::from_bytes(&bytes[1..])),
+            PacketKind::Queued => Packet::Queued(Queued::from_bytes(&bytes[1..])),
         };
 
         Some(packet)
@@ -121,7 +197,10 @@ impl Packet {
                 let mut bytes = connection_request.as_bytes();
                 output.append(&mut bytes);
             }
-            Packet::ChallengeRequest => {}
+            Packet::ChallengeRequest(challenge_request) => {
+                let mut bytes = challenge_request.as_bytes();
+                output.append(&mut bytes);
+            }
             Packet::ChallengeResponse(challenge_response) => {
                 let mut bytes = challenge_response.as_bytes();
                 output.append(&mut bytes);
@@ -138,7 +217,34 @@ impl Packet {
                 let mut bytes = data.as_bytes();
                 output.append(&mut bytes);
             }
-            Packet::Unimplemented => {}
+            Packet::Ping(ping) => {
+                let mut bytes = ping.as_bytes();
+                output.append(&mut bytes);
+            }
+            Packet::Pong(pong) => {
+                let mut bytes = pong.as_bytes();
+                output.append(&mut bytes);
+            }
+            Packet::MtuProbe(mtu_probe) => {
+                let mut bytes = mtu_probe.as_bytes();
+                output.append(&mut bytes);
+            }
+            Packet::MtuProbeAck(mtu_probe_ack) => {
+                let mut bytes = mtu_probe_ack.as_bytes();
+                output.append(&mut bytes);
+            }
+            Packet::PathChallenge(path_challenge) => {
+                let mut bytes = path_challenge.as_bytes();
+                output.append(&mut bytes);
+            }
+            Packet::PathResponse(path_response) => {
+                let mut bytes = path_response.as_bytes();
+                output.append(&mut bytes);
+            }
+            Packet::Queued(queued) => {
+                let mut bytes = queued.as_bytes();
+                output.append(&mut bytes);
+            }
         }
 
         output
@@ -147,40 +253,84 @@ impl Packet {
     pub fn kind(&self) -> PacketKind {
         match self {
             Packet::ConnectionRequest(_) => PacketKind::ConnectionRequest,
-            Packet::ChallengeRequest => PacketKind::ChallengeRequest,
+            Packet::ChallengeRequest(_) => PacketKind::ChallengeRequest,
             Packet::ChallengeResponse(_) => PacketKind::ChallengeResponse,
             Packet::KeepAlive(_) => PacketKind::KeepAlive,
             Packet::Data(_) => PacketKind::Data,
             Packet::Disconnect(_) => PacketKind::Disconnect,
-            Packet::Unimplemented => PacketKind::Unimplemented,
+            Packet::Ping(_) => PacketKind::Ping,
+            Packet::Pong(_) => PacketKind::Pong,
+            Packet::MtuProbe(_) => PacketKind::MtuProbe,
+            Packet::MtuProbeAck(_) => PacketKind::MtuProbeAck,
+            Packet::PathChallenge(_) => PacketKind::PathChallenge,
+            Packet::PathResponse(_) => PacketKind::PathResponse,
+            Packet::Queued(_) => PacketKind::Queued,
         }
     }
 
     pub fn header(&self) -> Header {
         match self {
             Packet::ConnectionRequest(connection_request) => connection_request.header,
-            Packet::ChallengeRequest => todo!(),
+            Packet::ChallengeRequest(challenge_request) => challenge_request.header,
             Packet::ChallengeResponse(challenge_response) => challenge_response.header,
             Packet::KeepAlive(keep_alive) => keep_alive.header,
             Packet::Data(data) => data.header,
             Packet::Disconnect(disconnect) => disconnect.header,
-            Packet::Unimplemented => todo!(),
+            Packet::Ping(ping) => ping.header,
+            Packet::Pong(pong) => pong.header,
+            Packet::MtuProbe(mtu_probe) => mtu_probe.header,
+            Packet::MtuProbeAck(mtu_probe_ack) => mtu_probe_ack.header,
+            Packet::PathChallenge(path_challenge) => path_challenge.header,
+            Packet::PathResponse(path_response) => path_response.header,
+            Packet::Queued(queued) => queued.header,
         }
     }
 
     pub fn set_sequence(&mut self, sequence: u64) {
         match self {
-            Packet::ConnectionRequest(mut connection_request) => {
+            Packet::ConnectionRequest(connection_request) => {
                 connection_request.header.sequence = sequence
             }
-            Packet::ChallengeRequest => {}
+            Packet::ChallengeRequest(challenge_request) => {
+                challenge_request.header.sequence = sequence
+            }
             Packet::ChallengeResponse(challenge_response) => {
                 challenge_response.header.sequence = sequence
             }
             Packet::KeepAlive(keep_alive) => keep_alive.header.sequence = sequence,
             Packet::Data(data) => data.header.sequence = sequence,
             Packet::Disconnect(disconnect) => disconnect.header.sequence = sequence,
-            Packet::Unimplemented => {}
+            Packet::Ping(ping) => ping.header.sequence = sequence,
+            Packet::Pong(pong) => pong.header.sequence = sequence,
+            Packet::MtuProbe(mtu_probe) => mtu_probe.header.sequence = sequence,
+            Packet::MtuProbeAck(mtu_probe_ack) => mtu_probe_ack.header.sequence = sequence,
+            Packet::PathChallenge(path_challenge) => path_challenge.header.sequence = sequence,
+            Packet::PathResponse(path_response) => path_response.header.sequence = sequence,
+            Packet::Queued(queued) => queued.header.sequence = sequence,
+        }
+    }
+
+    pub fn set_client_id(&mut self, client_id: UnetId) {
+        match self {
+            Packet::ConnectionRequest(connection_request) => {
+                connection_request.header.client_id = client_id
+            }
+            Packet::ChallengeRequest(challenge_request) => {
+                challenge_request.header.client_id = client_id
+            }
+            Packet::ChallengeResponse(challenge_response) => {
+                challenge_response.header.client_id = client_id
+            }
+            Packet::KeepAlive(keep_alive) => keep_alive.header.client_id = client_id,
+            Packet::Data(data) => data.header.client_id = client_id,
+            Packet::Disconnect(disconnect) => disconnect.header.client_id = client_id,
+            Packet::Ping(ping) => ping.header.client_id = client_id,
+            Packet::Pong(pong) => pong.header.client_id = client_id,
+            Packet::MtuProbe(mtu_probe) => mtu_probe.header.client_id = client_id,
+            Packet::MtuProbeAck(mtu_probe_ack) => mtu_probe_ack.header.client_id = client_id,
+            Packet::PathChallenge(path_challenge) => path_challenge.header.client_id = client_id,
+            Packet::PathResponse(path_response) => path_response.header.client_id = client_id,
+            Packet::Queued(queued) => queued.header.client_id = client_id,
         }
     }
 }
@@ -204,6 +354,12 @@ impl Header {
         }
     }
 
+    /// Client id of a packet that hasn't been decoded yet, `bytes` start with the packet kind.
+    pub fn peek_client_id(bytes: &[u8]) -> Option<UnetId> {
+        let client_id = bytes.get(6..14)?;
+        Some(UnetId(u64::from_be_bytes(client_id.try_into().unwrap())))
+    }
+
     pub fn from_bytes(bytes: &[u8]) -> Self {
         assert_eq!(bytes.len(), Self::SIZE);
 
@@ -234,7 +390,22 @@ impl Header {
 
 #[cfg(test)]
 mod tests {
-    use crate::packet::{Header, UnetId};
+    use crate::cookie::Cookie;
+    use crate::packet::challenge_request::ChallengeRequest;
+    use crate::packet::challenge_response::ChallengeResponse;
+    use crate::packet::connection_request::ConnectionRequest;
+    use crate::packet::data::Data;
+    use crate::packet::disconnect::{Disconnect, DisconnectReason};
+    use crate::packet::keep_alive::KeepAlive;
+    use crate::packet::mtu_probe::MtuProbe;
+    use crate::packet::mtu_probe_ack::MtuProbeAck;
+    use crate::packet::path_challenge::PathChallenge;
+    use crate::packet::path_response::PathResponse;
+    use crate::packet::ping::Ping;
+    use crate::packet::pong::Pong;
+    use crate::packet::queued::Queued;
+    use crate::packet::{Header, Packet, UnetId};
+    use crate::user_data::UserData;
 
     #[test]
     fn from_bytes() {
@@ -247,6 +418,84 @@ mod tests {
         assert_eq!(header.sequence, 123);
     }
 
+    #[test]
+    fn peek_client_id() {
+        let packet = Packet::MtuProbe(MtuProbe::new(UnetId(999), 1200));
+        assert_eq!(
+            Header::peek_client_id(&packet.as_bytes()),
+            Some(UnetId(999))
+        );
+        assert_eq!(Header::peek_client_id(&[4, 85, 78]), None);
+    }
+
+    #[test]
+    fn mtu_probe_is_padded() {
+        let packet = Packet::MtuProbe(MtuProbe::new(UnetId(999), 1200));
+        let bytes = packet.as_bytes();
+        assert_eq!(bytes.len(), 1200);
+        assert_eq!(Packet::from_bytes(&bytes), Some(packet));
+    }
+
+    #[test]
+    fn connection_request_is_padded() {
+        let packet = Packet::ConnectionRequest(ConnectionRequest::new(UnetId(999)));
+        let bytes = packet.as_bytes();
+        let challenge_request = ChallengeRequest::new(UnetId(999), Cookie::default());
+        assert_eq!(
+            bytes.len(),
+            Packet::ChallengeRequest(challenge_request).as_bytes().len()
+        );
+        assert_eq!(Packet::from_bytes(&bytes), Some(packet));
+        assert_eq!(Packet::from_bytes(&bytes[..bytes.len() - 1]), None);
+    }
+
+    #[test]
+    fn short_packets_are_rejected() {
+        let id = UnetId(999);
+        let packets = [
+            Packet::ConnectionRequest(ConnectionRequest::new(id)),
+            Packet::ChallengeRequest(ChallengeRequest::new(id, Cookie::default())),
+            Packet::ChallengeResponse(ChallengeResponse::new(id, 1, Cookie::default())),
+            Packet::KeepAlive(KeepAlive::new(id)),
+            Packet::Data(Data::new(id, 1)),
+            Packet::Disconnect(Disconnect::new(id, DisconnectReason::Timeout)),
+            Packet::Ping(Ping::new(id, 1)),
+            Packet::Pong(Pong::new(id, 1)),
+            Packet::MtuProbe(MtuProbe::new(id, MtuProbe::MIN_SIZE as u16)),
+            Packet::MtuProbeAck(MtuProbeAck::new(id, 1200)),
+            Packet::PathChallenge(PathChallenge::new(id, 1)),
+            Packet::PathResponse(PathResponse::new(id, 1, 1)),
+            Packet::Queued(Queued::new(id, 1)),
+        ];
+        for packet in packets {
+            let bytes = packet.as_bytes();
+            assert_eq!(bytes.len() - 1, packet.kind().min_size());
+            assert_eq!(Packet::from_bytes(&bytes), Some(packet));
+            for len in 0..bytes.len() {
+                assert_eq!(Packet::from_bytes(&bytes[..len]), None);
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_user_data_is_rejected() {
+        let mut challenge_response = ChallengeResponse::new(UnetId(999), 1, Cookie::default());
+        challenge_response.user_data = UserData::new(b"player one").unwrap();
+        let bytes = Packet::ChallengeResponse(challenge_response).as_bytes();
+        assert!(Packet::from_bytes(&bytes).is_some());
+        assert_eq!(Packet::from_bytes(&bytes[..bytes.len() - 1]), None);
+    }
+
+    #[test]
+    fn unknown_values_are_rejected() {
+        assert_eq!(Packet::from_bytes(&[200; 40]), None);
+
+        let disconnect = Disconnect::new(UnetId(999), DisconnectReason::Timeout);
+        let mut bytes = Packet::Disconnect(disconnect).as_bytes();
+        *bytes.last_mut().unwrap() = 200;
+        assert_eq!(Packet::from_bytes(&bytes), None);
+    }
+
     #[test]
     fn as_bytes() {
         let mut header = Header::new(UnetId(999));
@@ -257,4 +506,26 @@ mod tests {
             vec![85, 78, 69, 84, 49, 0, 0, 0, 0, 0, 0, 3, 231, 0, 0, 0, 0, 0, 0, 0, 123]
         )
     }
+
+    #[test]
+    fn set_sequence() {
+        let mut packet = Packet::ConnectionRequest(ConnectionRequest::new(UnetId(999)));
+        packet.set_sequence(123);
+        assert_eq!(packet.header().sequence, 123);
+        assert_eq!(Packet::from_bytes(&packet.as_bytes()), Some(packet));
+    }
+
+    #[test]
+    fn path_response_proves_secret() {
+        let path_response = PathResponse::new(UnetId(999), 42, 1234);
+        assert!(path_response.proves(1234));
+        assert!(!path_response.proves(1235));
+
+        let packet = Packet::PathResponse(path_response);
+        let bytes = packet.as_bytes();
+        assert!(!bytes
+            .windows(8)
+            .any(|window| window == 1234u64.to_be_bytes()));
+        assert_eq!(Packet::from_bytes(&bytes), Some(packet));
+    }
 }
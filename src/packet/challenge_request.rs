@@ -0,0 +1,33 @@
+use crate::cookie::Cookie;
+use crate::packet::{Header, UnetId};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ChallengeRequest {
+    pub header: Header,
+    pub cookie: Cookie, // Has to come back in the ChallengeResponse
+}
+
+impl ChallengeRequest {
+    pub const SIZE: usize = Header::SIZE + Cookie::SIZE;
+
+    pub fn new(client_id: UnetId, cookie: Cookie) -> Self {
+        Self {
+            header: Header::new(client_id),
+            cookie,
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let header = Header::from_bytes(&bytes[..Header::SIZE]);
+        let cookie = Cookie::from_bytes(&bytes[Header::SIZE..Header::SIZE + Cookie::SIZE]);
+
+        Self { header, cookie }
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut output = vec![];
+        output.append(&mut self.header.as_bytes());
+        output.append(&mut self.cookie.as_bytes());
+        output
+    }
+}
@@ -1,25 +1,55 @@
+use crate::cookie::Cookie;
 use crate::packet::{Header, UnetId};
+use crate::user_data::UserData;
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct ChallengeResponse {
     pub header: Header,
+    pub secret: u64,    // Proves it's still the same client when its address changes
+    pub cookie: Cookie, // From the ChallengeRequest
+    pub priority: u8,   // Same as in the ConnectionRequest, the cookie is bound to it
+    pub user_data: UserData, // Only sent once the client proved its address
 }
 
 impl ChallengeResponse {
-    pub fn new(client_id: UnetId) -> Self {
+    /// Without any user data, which comes length prefixed.
+    pub const MIN_SIZE: usize =
+        Header::SIZE + size_of::<u64>() + Cookie::SIZE + size_of::<u8>() + size_of::<u16>();
+
+    pub fn new(client_id: UnetId, secret: u64, cookie: Cookie) -> Self {
         Self {
             header: Header::new(client_id),
+            secret,
+            cookie,
+            priority: 0,
+            user_data: UserData::default(),
         }
     }
 
-    pub fn from_bytes(bytes: &[u8]) -> Self {
-        let header = Header::from_bytes(bytes);
-        Self { header }
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let header = Header::from_bytes(&bytes[..Header::SIZE]);
+        let secret = u64::from_be_bytes(bytes[Header::SIZE..Header::SIZE + 8].try_into().unwrap());
+        let cookie_start = Header::SIZE + 8;
+        let cookie = Cookie::from_bytes(&bytes[cookie_start..cookie_start + Cookie::SIZE]);
+        let priority = bytes[cookie_start + Cookie::SIZE];
+        let user_data = UserData::from_bytes(&bytes[cookie_start + Cookie::SIZE + 1..])?;
+
+        Some(Self {
+            header,
+            secret,
+            cookie,
+            priority,
+            user_data,
+        })
     }
 
     pub fn as_bytes(&self) -> Vec<u8> {
         let mut output = vec![];
         output.append(&mut self.header.as_bytes());
+        output.append(&mut self.secret.to_be_bytes().to_vec());
+        output.append(&mut self.cookie.as_bytes());
+        output.push(self.priority);
+        output.append(&mut self.user_data.as_bytes());
         output
     }
 }
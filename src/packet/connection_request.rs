@@ -1,26 +1,35 @@
+use crate::packet::challenge_request::ChallengeRequest;
 use crate::packet::{Header, UnetId};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct ConnectionRequest {
     pub header: Header,
+    pub priority: u8, // 0 for regular clients, see ReservedSlotsConfig
 }
 
 impl ConnectionRequest {
+    /// Padded to the size of our answer, so a spoofed request can't get back more than it sent.
+    pub const SIZE: usize = ChallengeRequest::SIZE;
+
     pub fn new(client_id: UnetId) -> Self {
         Self {
             header: Header::new(client_id),
+            priority: 0,
         }
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Self {
         let header = Header::from_bytes(&bytes[..Header::SIZE]);
+        let priority = bytes[Header::SIZE];
 
-        Self { header }
+        Self { header, priority }
     }
 
     pub fn as_bytes(&self) -> Vec<u8> {
         let mut output = vec![];
         output.append(&mut self.header.as_bytes());
+        output.push(self.priority);
+        output.resize(Self::SIZE, 0);
         output
     }
 }
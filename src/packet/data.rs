@@ -7,6 +7,8 @@ pub struct Data {
 }
 
 impl Data {
+    pub const SIZE: usize = Header::SIZE + size_of::<i32>();
+
     pub fn new(client_id: UnetId, val: i32) -> Self {
         Self {
             header: Header::new(client_id),
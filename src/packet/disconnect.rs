@@ -7,6 +7,10 @@ pub enum DisconnectReason {
     ServerFull = 1,
     Spam = 2,
     ConnectionResetByPeer = 3,
+    Banned = 4,
+    Rejected = 5, // Turned away by the server's AcceptHook
+    ServerShutdown = 6,
+    Evicted = 7, // Made room for a client with a higher priority
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -16,18 +20,25 @@ pub struct Disconnect {
 }
 
 impl DisconnectReason {
-    pub fn from_byte(byte: u8) -> Self {
-        match byte {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        let reason = match byte {
             0 => Self::Timeout,
             1 => Self::ServerFull,
             2 => Self::Spam,
             3 => Self::ConnectionResetByPeer,
-            _ => panic!("Badly formed DisconnectReason value: {byte}"),
-        }
+            4 => Self::Banned,
+            5 => Self::Rejected,
+            6 => Self::ServerShutdown,
+            7 => Self::Evicted,
+            _ => return None,
+        };
+        Some(reason)
     }
 }
 
 impl Disconnect {
+    pub const SIZE: usize = Header::SIZE + size_of::<DisconnectReason>();
+
     pub fn new(client_id: UnetId, reason: DisconnectReason) -> Self {
         Self {
             header: Header::new(client_id),
@@ -35,11 +46,11 @@ impl Disconnect {
         }
     }
 
-    pub fn from_bytes(bytes: &[u8]) -> Self {
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
         let header = Header::from_bytes(&bytes[..Header::SIZE]);
-        let reason = DisconnectReason::from_byte(bytes[Header::SIZE]);
+        let reason = DisconnectReason::from_byte(bytes[Header::SIZE])?;
 
-        Self { header, reason }
+        Some(Self { header, reason })
     }
 
     pub fn as_bytes(&self) -> Vec<u8> {
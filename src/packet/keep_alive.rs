@@ -6,6 +6,8 @@ pub struct KeepAlive {
 }
 
 impl KeepAlive {
+    pub const SIZE: usize = Header::SIZE;
+
     pub fn new(client_id: UnetId) -> Self {
         Self {
             header: Header::new(client_id),
@@ -13,7 +15,7 @@ impl KeepAlive {
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Self {
-        let header = Header::from_bytes(&bytes);
+        let header = Header::from_bytes(&bytes[..Header::SIZE]);
         Self { header }
     }
 
@@ -0,0 +1,35 @@
+use crate::packet::{Header, UnetId};
+
+/// Padded with zeroes up to `size` bytes on the wire, packet kind byte included.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct MtuProbe {
+    pub header: Header,
+    pub size: u16,
+}
+
+impl MtuProbe {
+    pub const MIN_SIZE: usize = 1 + Header::SIZE + size_of::<u16>();
+
+    pub fn new(client_id: UnetId, size: u16) -> Self {
+        assert!(size as usize >= Self::MIN_SIZE);
+        Self {
+            header: Header::new(client_id),
+            size,
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let header = Header::from_bytes(&bytes[..Header::SIZE]);
+        let size = u16::from_be_bytes([bytes[Header::SIZE], bytes[Header::SIZE + 1]]);
+
+        Self { header, size }
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut output = vec![];
+        output.append(&mut self.header.as_bytes());
+        output.append(&mut self.size.to_be_bytes().to_vec());
+        output.resize(self.size as usize - 1, 0);
+        output
+    }
+}
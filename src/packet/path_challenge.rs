@@ -0,0 +1,34 @@
+use crate::packet::{Header, UnetId};
+
+/// Sent by the server to a new address a known client showed up from.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PathChallenge {
+    pub header: Header,
+    pub challenge: u64,
+}
+
+impl PathChallenge {
+    pub const SIZE: usize = Header::SIZE + size_of::<u64>();
+
+    pub fn new(client_id: UnetId, challenge: u64) -> Self {
+        Self {
+            header: Header::new(client_id),
+            challenge,
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let header = Header::from_bytes(&bytes[..Header::SIZE]);
+        let challenge =
+            u64::from_be_bytes(bytes[Header::SIZE..Header::SIZE + 8].try_into().unwrap());
+
+        Self { header, challenge }
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut output = vec![];
+        output.append(&mut self.header.as_bytes());
+        output.append(&mut self.challenge.to_be_bytes().to_vec());
+        output
+    }
+}
@@ -0,0 +1,64 @@
+use crate::packet::{Header, UnetId};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+const PROOF_SIZE: usize = 16; // Truncated HMAC-SHA256
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PathResponse {
+    pub header: Header,
+    pub challenge: u64, // Echoed from the PathChallenge, proves the new address is reachable
+    pub proof: [u8; PROOF_SIZE], // HMAC of the challenge, keyed with the connection's secret
+}
+
+impl PathResponse {
+    pub const SIZE: usize = Header::SIZE + size_of::<u64>() + PROOF_SIZE;
+
+    /// The secret itself never goes out again, anyone who saw it could take over the connection.
+    pub fn new(client_id: UnetId, challenge: u64, secret: u64) -> Self {
+        let proof = Self::hmac(challenge, secret).finalize().into_bytes()[..PROOF_SIZE]
+            .try_into()
+            .unwrap();
+        Self {
+            header: Header::new(client_id),
+            challenge,
+            proof,
+        }
+    }
+
+    /// True if whoever sent this knows `secret`.
+    pub fn proves(&self, secret: u64) -> bool {
+        Self::hmac(self.challenge, secret)
+            .verify_truncated_left(&self.proof)
+            .is_ok()
+    }
+
+    fn hmac(challenge: u64, secret: u64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&secret.to_be_bytes()).unwrap();
+        mac.update(&challenge.to_be_bytes());
+        mac
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let header = Header::from_bytes(&bytes[..Header::SIZE]);
+        let challenge =
+            u64::from_be_bytes(bytes[Header::SIZE..Header::SIZE + 8].try_into().unwrap());
+        let proof = bytes[Header::SIZE + 8..Header::SIZE + 8 + PROOF_SIZE]
+            .try_into()
+            .unwrap();
+
+        Self {
+            header,
+            challenge,
+            proof,
+        }
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut output = vec![];
+        output.append(&mut self.header.as_bytes());
+        output.append(&mut self.challenge.to_be_bytes().to_vec());
+        output.extend_from_slice(&self.proof);
+        output
+    }
+}
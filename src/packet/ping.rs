@@ -0,0 +1,41 @@
+use crate::packet::{Header, UnetId};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Ping {
+    pub header: Header,
+    pub ping_id: u64,
+}
+
+impl Ping {
+    pub const SIZE: usize = Header::SIZE + size_of::<u64>();
+
+    pub fn new(client_id: UnetId, ping_id: u64) -> Self {
+        Self {
+            header: Header::new(client_id),
+            ping_id,
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let header = Header::from_bytes(&bytes[..Header::SIZE]);
+        let ping_id = u64::from_be_bytes([
+            bytes[Header::SIZE],
+            bytes[Header::SIZE + 1],
+            bytes[Header::SIZE + 2],
+            bytes[Header::SIZE + 3],
+            bytes[Header::SIZE + 4],
+            bytes[Header::SIZE + 5],
+            bytes[Header::SIZE + 6],
+            bytes[Header::SIZE + 7],
+        ]);
+
+        Self { header, ping_id }
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut output = vec![];
+        output.append(&mut self.header.as_bytes());
+        output.append(&mut self.ping_id.to_be_bytes().to_vec());
+        output
+    }
+}
@@ -0,0 +1,33 @@
+use crate::packet::{Header, UnetId};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Queued {
+    pub header: Header,
+    pub position: u32, // 1 is next in line
+}
+
+impl Queued {
+    pub const SIZE: usize = Header::SIZE + size_of::<u32>();
+
+    pub fn new(client_id: UnetId, position: u32) -> Self {
+        Self {
+            header: Header::new(client_id),
+            position,
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let header = Header::from_bytes(&bytes[..Header::SIZE]);
+        let position =
+            u32::from_be_bytes(bytes[Header::SIZE..Header::SIZE + 4].try_into().unwrap());
+
+        Self { header, position }
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut output = vec![];
+        output.append(&mut self.header.as_bytes());
+        output.append(&mut self.position.to_be_bytes().to_vec());
+        output
+    }
+}
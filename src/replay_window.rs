@@ -0,0 +1,146 @@
+const WINDOW_SIZE: u64 = 256;
+const WORDS: usize = (WINDOW_SIZE / 64) as usize;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ReplayCheck {
+    New,       // Newest sequence so far
+    Late,      // Older than the newest, but not seen before
+    Duplicate, // Seen before
+    TooOld,    // Fell out of the window, can't tell if it was seen before
+}
+
+impl ReplayCheck {
+    pub fn is_accepted(&self) -> bool {
+        matches!(self, ReplayCheck::New | ReplayCheck::Late)
+    }
+}
+
+/// Remembers which of the last `WINDOW_SIZE` sequences were received, so packets that were
+/// only reordered are still accepted while duplicates and replays are not.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayWindow {
+    highest: Option<u64>,
+    seen: [u64; WORDS], // Bit n is set if `highest - n` was received
+}
+
+impl ReplayWindow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn highest(&self) -> Option<u64> {
+        self.highest
+    }
+
+    /// Checks `sequence` and marks it as received if it's accepted.
+    pub fn receive(&mut self, sequence: u64) -> ReplayCheck {
+        let Some(highest) = self.highest else {
+            self.highest = Some(sequence);
+            self.set(0);
+            return ReplayCheck::New;
+        };
+
+        if sequence > highest {
+            self.shift(sequence - highest);
+            self.highest = Some(sequence);
+            self.set(0);
+            return ReplayCheck::New;
+        }
+
+        let offset = highest - sequence;
+        if offset >= WINDOW_SIZE {
+            return ReplayCheck::TooOld;
+        }
+
+        if self.is_set(offset) {
+            return ReplayCheck::Duplicate;
+        }
+
+        self.set(offset);
+        ReplayCheck::Late
+    }
+
+    fn set(&mut self, offset: u64) {
+        self.seen[(offset / 64) as usize] |= 1 << (offset % 64);
+    }
+
+    fn is_set(&self, offset: u64) -> bool {
+        self.seen[(offset / 64) as usize] & (1 << (offset % 64)) != 0
+    }
+
+    // Everything we've seen gets `by` older
+    fn shift(&mut self, by: u64) {
+        if by >= WINDOW_SIZE {
+            self.seen = [0; WORDS];
+            return;
+        }
+
+        let words = (by / 64) as usize;
+        let bits = (by % 64) as u32;
+        for i in (0..WORDS).rev() {
+            let mut word = 0;
+            if i >= words {
+                word = self.seen[i - words] << bits;
+                if bits > 0 && i > words {
+                    word |= self.seen[i - words - 1] >> (64 - bits);
+                }
+            }
+            self.seen[i] = word;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::replay_window::{ReplayCheck, ReplayWindow};
+
+    #[test]
+    fn in_order() {
+        let mut window = ReplayWindow::new();
+        for sequence in 0..1000 {
+            assert_eq!(window.receive(sequence), ReplayCheck::New);
+        }
+        assert_eq!(window.highest(), Some(999));
+    }
+
+    #[test]
+    fn late_but_unseen() {
+        let mut window = ReplayWindow::new();
+        assert_eq!(window.receive(0), ReplayCheck::New);
+        assert_eq!(window.receive(2), ReplayCheck::New);
+        assert_eq!(window.receive(1), ReplayCheck::Late);
+        assert_eq!(window.receive(1), ReplayCheck::Duplicate);
+        assert_eq!(window.receive(2), ReplayCheck::Duplicate);
+    }
+
+    #[test]
+    fn lost_packet_doesnt_desync() {
+        let mut window = ReplayWindow::new();
+        window.receive(0);
+        window.receive(2); // 1 was lost
+        assert_eq!(window.receive(3), ReplayCheck::New);
+        assert_eq!(window.receive(4), ReplayCheck::New);
+    }
+
+    #[test]
+    fn across_words() {
+        let mut window = ReplayWindow::new();
+        window.receive(10);
+        window.receive(100);
+        window.receive(200);
+        assert_eq!(window.receive(10), ReplayCheck::Duplicate);
+        assert_eq!(window.receive(100), ReplayCheck::Duplicate);
+        assert_eq!(window.receive(11), ReplayCheck::Late);
+        assert_eq!(window.receive(199), ReplayCheck::Late);
+    }
+
+    #[test]
+    fn too_old() {
+        let mut window = ReplayWindow::new();
+        window.receive(0);
+        window.receive(300);
+        assert_eq!(window.receive(0), ReplayCheck::TooOld);
+        assert_eq!(window.receive(44), ReplayCheck::TooOld);
+        assert_eq!(window.receive(45), ReplayCheck::Late);
+    }
+}
@@ -3,18 +3,13 @@ use std::collections::VecDeque;
 #[derive(Clone, Debug)]
 pub struct RollingAverage {
     pub(crate) values: VecDeque<f32>,
-    value: f32,
     n: usize,
 }
 
 impl RollingAverage {
     pub fn new(n: usize) -> Self {
         let values = VecDeque::from(vec![0.0; n]);
-        Self {
-            values,
-            value: 0.0,
-            n,
-        }
+        Self { values, n }
     }
 
     pub fn add(&mut self, value: f32) {
@@ -1,52 +1,127 @@
+pub mod accept;
+pub mod ban;
 pub mod connection;
+pub mod event;
+pub mod rate_limit;
+pub mod reserved_slots;
+pub mod spam;
+pub mod waiting_queue;
 
+use crate::clock::{Clock, SystemClock};
 use crate::config::server::ServerConfig;
-use crate::debug::{client_connect_dbg, client_disconnect_dbg, recv_dbg, send_dbg, YELLOW};
-use crate::network::Network;
-use crate::network::Network::{Real, Virtual};
+use crate::congestion::CongestionController;
+use crate::cookie::{CookieKey, UsedCookies};
+use crate::debug::{
+    client_connect_dbg, client_disconnect_dbg, client_migrate_dbg, recv_dbg, send_dbg, YELLOW,
+};
+use crate::mtu::MtuDiscovery;
+use crate::network::udp::UdpTransport;
+use crate::network::Transport;
+use crate::packet::challenge_request::ChallengeRequest;
+use crate::packet::challenge_response::ChallengeResponse;
 use crate::packet::disconnect::{Disconnect, DisconnectReason};
 use crate::packet::keep_alive::KeepAlive;
-use crate::packet::Packet;
+use crate::packet::mtu_probe::MtuProbe;
+use crate::packet::mtu_probe_ack::MtuProbeAck;
+use crate::packet::path_challenge::PathChallenge;
+use crate::packet::path_response::PathResponse;
+use crate::packet::ping::Ping;
+use crate::packet::pong::Pong;
+use crate::packet::queued::Queued;
+use crate::packet::{Header, Packet, PacketKind, UnetId};
+use crate::server::accept::{AcceptHook, PendingConnection};
+use crate::server::ban::{BanList, BanTarget};
 use crate::server::connection::{Connection, ConnectionIdentifier};
+use crate::server::event::ServerEvent;
+use crate::server::rate_limit::{RateLimitStats, RateLimiter};
+use crate::server::spam::{SpamAction, SpamFilter};
+use crate::server::waiting_queue::WaitingQueue;
+use crate::stats::{NetworkStats, StatsTracker};
 use crate::tick::Tick;
-use crate::{BUF_SIZE, MAX_CONNECTIONS};
+use crate::MAX_PACKET_SIZE;
 use colored::Colorize;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::io;
-use std::net::{SocketAddr, UdpSocket};
-use std::thread::sleep;
-use std::time::{Duration, Instant};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant, SystemTime};
+
+const SHUTDOWN_DISCONNECT_REPEATS: usize = 3; // Disconnects sent per client, in case some are lost
 
 #[derive(Debug)]
 pub struct UnetServer {
-    network: Network,
+    transport: Box<dyn Transport>,
     pub connections: Vec<Option<Connection>>,
-    receive_buffer: VecDeque<(Packet, SocketAddr)>,
+    connection_slots: HashMap<ConnectionIdentifier, usize>, // Slot in `connections` per client
+    connection_ids: HashMap<UnetId, usize>, // Same, but by id only, for finding migrating clients
+    client_slots: HashMap<ConnectionIdentifier, usize>, // Same, by the id the client picked
+    free_slots: Vec<usize>, // Vacant slots in `connections`, next one to use is last
+    priority_connections: usize, // Connections that count as priority clients for reserved slots
+    receive_buffer: VecDeque<(Packet, SocketAddr, usize)>, // Packet, sender, size in bytes
+    events: VecDeque<ServerEvent>,
+    rate_limiter: Option<RateLimiter>, // Drops floods from a single IP before they're decoded
+    waiting_queue: Option<WaitingQueue>, // Clients waiting for a slot when we're full
+    accept_hook: Option<Box<dyn AcceptHook>>, // Application's say on who gets in
+    ban_list: BanList,
+    cookie_key: CookieKey, // Signs the challenge cookies, so the handshake needs no state
+    used_cookies: UsedCookies, // Until they expire, so handshakes can't be replayed
+    shutting_down: bool,   // New clients are turned away
+    started_at: (Instant, SystemTime), // Ban expiry is wall clock time, this maps `clock` to it
     config: ServerConfig,
     global_tick: Tick,
+    clock: Box<dyn Clock>,
     previous: Instant,
     lag: u128,
 }
 
 impl UnetServer {
     pub fn from_config(mut config: ServerConfig) -> io::Result<Self> {
-        let network = if let Some(virtual_network) = config.virtual_network.take() {
-            Virtual(virtual_network)
+        let transport = if let Some(transport) = config.transport.take() {
+            transport
         } else {
-            let socket = UdpSocket::bind(config.addr)?;
-            socket.set_nonblocking(true)?;
-            Real(socket)
+            Box::new(UdpTransport::bind(
+                &config.listen_addrs(),
+                config.dual_stack,
+            )?)
         };
 
-        let connections = vec![None; MAX_CONNECTIONS];
+        let clock = config.clock.take().unwrap_or_else(|| Box::new(SystemClock));
+
+        let rate_limiter = config
+            .rate_limit
+            .map(|rate_limit_config| RateLimiter::new(rate_limit_config, config.tps));
+
+        let waiting_queue = config
+            .waiting_queue
+            .map(|waiting_queue_config| WaitingQueue::new(waiting_queue_config, config.tps));
+
+        let accept_hook = config.accept_hook.take();
+        let ban_list = std::mem::take(&mut config.ban_list);
+
+        let connections = vec![None; config.max_connections];
+        let free_slots = (0..config.max_connections).rev().collect();
 
         let server = Self {
-            network,
+            transport,
             connections,
+            connection_slots: HashMap::new(),
+            connection_ids: HashMap::new(),
+            client_slots: HashMap::new(),
+            free_slots,
+            priority_connections: 0,
             receive_buffer: VecDeque::new(),
+            events: VecDeque::new(),
+            rate_limiter,
+            waiting_queue,
+            accept_hook,
+            ban_list,
+            cookie_key: CookieKey::new(),
+            used_cookies: UsedCookies::new(),
+            shutting_down: false,
+            started_at: (clock.now(), SystemTime::now()),
             config,
             global_tick: Tick { value: 0.0 },
-            previous: Instant::now(),
+            previous: clock.now(),
+            clock,
             lag: 0,
         };
 
@@ -56,15 +131,15 @@ impl UnetServer {
     }
 
     pub fn update(&mut self) {
-        let now = Instant::now();
-        let elapsed = self.previous.elapsed();
+        let now = self.clock.now();
+        let elapsed = now - self.previous;
 
         self.lag += elapsed.as_millis();
         if self.lag >= self.config.ms_per_tick {
             self.tick();
             self.lag -= self.config.ms_per_tick;
         } else {
-            sleep(Duration::from_millis(
+            self.clock.sleep(Duration::from_millis(
                 (self.config.ms_per_tick - self.lag) as u64,
             ));
         }
@@ -73,148 +148,910 @@ impl UnetServer {
     }
 
     pub fn tick(&mut self) {
-        self.reset_connections_stats();
+        self.release_throttled_packets();
         self.receive_packets();
         self.handle_packets();
         self.send_packets();
         // self.print_state(); // Ok to re-order this print
         self.kick_timed_out_connections();
-        self.kick_spamming_connections();
+        self.update_waiting_queue();
         self.tick_connections();
+        if self.once_a_second() {
+            self.prune_rate_limiter();
+            self.used_cookies
+                .remove_expired(self.global_tick, self.config.handshake_timeout);
+            let now = self.system_time();
+            self.ban_list.remove_expired(now);
+        }
 
         self.global_tick.value += 1.0;
     }
 
+    /// Stops taking new clients, gives the send queues up to `shutdown_timeout` to drain, then
+    /// tells everyone, connected or waiting, that we're going away. The server has no clients
+    /// once this returns.
+    pub fn shutdown(&mut self) {
+        self.shutting_down = true;
+
+        let deadline = self.clock.now() + self.config.shutdown_timeout;
+        while self.clock.now() < deadline
+            && self
+                .connections
+                .iter()
+                .flatten()
+                .any(|connection| !connection.send_queue.is_empty())
+        {
+            self.update();
+        }
+
+        let mut notify: Vec<_> = self
+            .connections
+            .iter()
+            .flatten()
+            .map(|connection| connection.connection_identifier)
+            .collect();
+        if let Some(waiting_queue) = self.waiting_queue.take() {
+            notify.extend(
+                waiting_queue
+                    .clients()
+                    .map(|client| client.client_identifier),
+            );
+        }
+
+        // Spread out a little, so they aren't all lost to the same burst
+        for repeat in 0..SHUTDOWN_DISCONNECT_REPEATS {
+            if repeat > 0 {
+                self.clock
+                    .sleep(Duration::from_millis(self.config.ms_per_tick as u64));
+            }
+            for &connection_identifier in &notify {
+                self.send_disconnect_packet(
+                    connection_identifier,
+                    DisconnectReason::ServerShutdown,
+                );
+            }
+        }
+
+        for connection_identifier in notify {
+            if let Some(index) =
+                self.find_client_index_by_connection_identifier(connection_identifier)
+            {
+                self.remove_connection(connection_identifier, index);
+            }
+        }
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down
+    }
+
+    pub fn poll_event(&mut self) -> Option<ServerEvent> {
+        self.events.pop_front()
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.transport.local_addr()
+    }
+
     fn send_to(&mut self, buf: &[u8], to: SocketAddr) -> io::Result<usize> {
-        self.network.send_to(buf, to)
+        self.transport.send_to(buf, to)
+    }
+
+    /// Queues `packet` for the client, it goes out with the next `send_packets` once the
+    /// connection is established. Returns false if there's no such connection.
+    pub fn send(&mut self, connection_identifier: ConnectionIdentifier, packet: Packet) -> bool {
+        let Some(connection) = self.get_connection(connection_identifier) else {
+            return false;
+        };
+
+        connection.send_queue.push_back(packet);
+        true
     }
 
     fn send_packet_to(
         &mut self,
-        packet: Packet,
+        mut packet: Packet,
         connection_identifier: ConnectionIdentifier,
     ) -> io::Result<usize> {
         let send_debug = self.config.send_debug;
         let to = connection_identifier.addr;
 
-        let mut index = None;
-        if let Some(connection) = self.get_connection(connection_identifier) {
-            connection.still_alive();
-            index = Some(connection.index);
-        }
+        let (bytes, index) = match self.get_connection(connection_identifier) {
+            Some(connection) => {
+                packet.set_sequence(connection.sequence);
+                connection.sequence += 1;
+                let bytes = packet.as_bytes();
+                connection.still_alive();
+                connection.stats.packet_sent(bytes.len());
+                (bytes, Some(connection.index))
+            }
+            None => (packet.as_bytes(), None),
+        };
 
         if send_debug {
             send_dbg(packet, Some(connection_identifier), index);
         }
 
-        let bytes = packet.as_bytes();
         self.send_to(&bytes, to)
     }
 
     fn send_packets(&mut self) {
-        for connection in self.connections.clone().into_iter().flatten() {
-            if connection.should_send_keep_alive() {
-                self.send_keep_alive_packet(connection.connection_identifier);
+        let now = self.clock.now();
+        for index in 0..self.connections.len() {
+            let Some(connection) = &mut self.connections[index] else {
+                continue;
+            };
+
+            let connection_identifier = connection.connection_identifier;
+            let ping_id = connection.stats.next_ping(now);
+            let probe = connection
+                .mtu_discovery
+                .as_mut()
+                .and_then(MtuDiscovery::next_probe);
+
+            let should_send_keep_alive =
+                connection.send_queue.is_empty() && connection.should_send_keep_alive();
+
+            if let Some(size) = probe {
+                self.send_mtu_probe_packet(connection_identifier, size);
+            }
+
+            if let Some(ping_id) = ping_id {
+                self.send_ping_packet(connection_identifier, ping_id);
+            } else if probe.is_none() && should_send_keep_alive {
+                self.send_keep_alive_packet(connection_identifier);
+            }
+
+            while let Some(packet) = self.next_queued_packet(index) {
+                self.send_packet_to(packet, connection_identifier).unwrap();
+            }
+        }
+    }
+
+    fn next_queued_packet(&mut self, index: usize) -> Option<Packet> {
+        let connection = self.connections[index].as_mut()?;
+        if connection.send_queue.is_empty() {
+            return None;
+        }
+
+        if let Some(congestion) = &mut connection.congestion {
+            if !congestion.try_send() {
+                return None;
             }
         }
+
+        connection.send_queue.pop_front()
     }
 
     fn receive(&self, buf: &mut [u8]) -> Option<(usize, SocketAddr)> {
-        self.network.recv_from(buf)
+        self.transport.recv_from(buf).ok()
     }
 
-    fn receive_packet(&self) -> Option<(Packet, SocketAddr)> {
-        let mut buf: [u8; BUF_SIZE] = [0; BUF_SIZE];
-        let (n, from) = self.receive(&mut buf)?;
-        assert_ne!(n, 0);
+    fn receive_packets(&mut self) {
+        let mut buf: [u8; MAX_PACKET_SIZE] = [0; MAX_PACKET_SIZE];
+        while let Some((n, from)) = self.receive(&mut buf) {
+            if n == 0 {
+                continue; // Legal UDP, but nothing we could have sent
+            }
+            let bytes = &buf[..n];
+
+            if !self.rate_limit(bytes, from) {
+                continue;
+            }
 
-        Some((Packet::from_bytes(&buf[..n])?, from))
+            if let Some(packet) = Packet::from_bytes(bytes) {
+                self.receive_buffer.push_back((packet, from, n));
+            }
+        }
     }
 
-    fn receive_packets(&mut self) {
-        while let Some((packet, from)) = self.receive_packet() {
-            self.receive_buffer.push_back((packet, from));
+    /// Returns false if the packet should be dropped without looking at it any further.
+    fn rate_limit(&mut self, bytes: &[u8], from: SocketAddr) -> bool {
+        let Some(rate_limiter) = &mut self.rate_limiter else {
+            return true;
+        };
+
+        let now = self.global_tick;
+        let ip = from.ip().to_canonical();
+        match PacketKind::from_byte(bytes[0]) {
+            Some(PacketKind::ConnectionRequest | PacketKind::ChallengeResponse) => {
+                rate_limiter.allow_handshake(ip, bytes.len(), now)
+            }
+            _ => {
+                let known = Header::peek_client_id(bytes).is_some_and(|id| {
+                    self.connection_slots
+                        .contains_key(&ConnectionIdentifier::new(id, from))
+                });
+                known || rate_limiter.allow_unknown(ip, bytes.len(), now)
+            }
         }
     }
 
+    fn prune_rate_limiter(&mut self) {
+        if let Some(rate_limiter) = &mut self.rate_limiter {
+            rate_limiter.prune(self.global_tick);
+        }
+    }
+
+    // For housekeeping that doesn't need to run every tick
+    fn once_a_second(&self) -> bool {
+        self.global_tick.value % self.config.tps.max(1.0).round() == 0.0
+    }
+
+    fn system_time(&self) -> SystemTime {
+        let (started, started_at) = self.started_at;
+        started_at + (self.clock.now() - started)
+    }
+
+    pub fn ban_list(&self) -> &BanList {
+        &self.ban_list
+    }
+
+    /// Changes apply from the next packet on.
+    pub fn ban_list_mut(&mut self) -> &mut BanList {
+        &mut self.ban_list
+    }
+
+    /// Bans `target` for `duration` (forever if None) and kicks every connection it matches, and
+    /// every client it matches that's waiting in line.
+    pub fn ban(&mut self, target: BanTarget, duration: Option<Duration>) {
+        let expires_at = duration.map(|duration| self.system_time() + duration);
+        self.ban_list.ban(target, expires_at);
+
+        let waiting = self
+            .waiting_queue
+            .as_mut()
+            .map(|waiting_queue| {
+                waiting_queue.remove_matching(|client| target.matches(client.client_identifier))
+            })
+            .unwrap_or_default();
+        for client in waiting {
+            self.send_disconnect_packet(client.client_identifier, DisconnectReason::Banned);
+        }
+
+        for index in 0..self.connections.len() {
+            let Some(connection) = &self.connections[index] else {
+                continue;
+            };
+
+            if target.matches(connection.client_identifier()) {
+                self.kick(connection.connection_identifier, DisconnectReason::Banned);
+            }
+        }
+    }
+
+    pub fn unban(&mut self, target: BanTarget) -> bool {
+        self.ban_list.unban(target)
+    }
+
+    /// Traffic dropped by the per-IP rate limits, None if they're off.
+    pub fn rate_limit_stats(&self) -> Option<RateLimitStats> {
+        Some(self.rate_limiter.as_ref()?.stats())
+    }
+
     fn handle_packets(&mut self) {
-        while let Some((packet, from)) = self.receive_buffer.pop_back() {
-            self.handle_packet(packet, from);
+        while let Some((packet, from, size)) = self.receive_buffer.pop_front() {
+            self.handle_packet(packet, from, size);
         }
     }
 
-    fn handle_packet(&mut self, packet: Packet, from: SocketAddr) {
+    fn handle_packet(&mut self, packet: Packet, from: SocketAddr, size: usize) {
         let header = packet.header();
         let connection_identifier = ConnectionIdentifier::new(header.client_id, from);
 
         let recv_debug = self.config.recv_debug;
 
+        // Bans by id are about the id the client picked, not its session
+        let client_identifier = match self.get_connection(connection_identifier) {
+            Some(connection) => connection.client_identifier(),
+            None => connection_identifier,
+        };
+        if self
+            .ban_list
+            .is_banned(client_identifier, self.system_time())
+        {
+            if recv_debug {
+                recv_dbg(packet, Some(connection_identifier), None);
+            }
+            if self.get_connection(connection_identifier).is_some() {
+                self.kick(connection_identifier, DisconnectReason::Banned);
+            } else if let Packet::ConnectionRequest(_) = packet {
+                self.send_disconnect_packet(connection_identifier, DisconnectReason::Banned);
+            }
+            return;
+        }
+
         if let Some(connection) = self.get_connection(connection_identifier) {
             if recv_debug {
                 recv_dbg(packet, Some(connection_identifier), Some(connection.index));
             }
 
-            if connection.is_packet_out_of_order(packet) {
+            connection.stats.packet_received(size);
+            if !self.check_spam(connection_identifier, packet, size) {
+                return;
+            }
+        } else {
+            if recv_debug {
+                recv_dbg(packet, Some(connection_identifier), None);
+            }
+
+            if self.handle_waiting_packet(packet, connection_identifier) {
+                return;
+            }
+
+            if self.config.connection_migration {
+                match packet {
+                    Packet::ConnectionRequest(_) | Packet::ChallengeResponse(_) => {}
+                    Packet::PathResponse(path_response) => {
+                        self.handle_path_response(path_response, from);
+                        return;
+                    }
+                    _ => {
+                        self.send_path_challenge(connection_identifier);
+                        return;
+                    }
+                }
+            }
+        }
+
+        self.process_packet(packet, from, size);
+    }
+
+    fn process_packet(&mut self, packet: Packet, from: SocketAddr, size: usize) {
+        let header = packet.header();
+        let connection_identifier = ConnectionIdentifier::new(header.client_id, from);
+
+        if let Some(connection) = self.get_connection(connection_identifier) {
+            if !connection.accept_sequence(packet) {
                 return;
             }
             connection.reset_timeout();
-            connection.packets_per_tick_received += 1.0;
-            connection.packet_sequence += 1;
-        } else if recv_debug {
-            recv_dbg(packet, Some(connection_identifier), None);
         }
 
         match packet {
             Packet::ConnectionRequest(connection_request) => {
-                self.handle_connection_request(connection_identifier);
+                self.handle_connection_request(connection_identifier, connection_request.priority);
             }
             Packet::ChallengeResponse(challenge_response) => {
-                let header = challenge_response.header;
-                let connection_identifier = ConnectionIdentifier::new(header.client_id, from);
-                self.accept_connection(connection_identifier)
+                self.accept_connection(connection_identifier, challenge_response);
             }
             Packet::Disconnect(disconnect) => {
                 let header = disconnect.header;
                 let connection_identifier = ConnectionIdentifier::new(header.client_id, from);
-                self.kick(connection_identifier, disconnect.reason);
+                if self.get_connection(connection_identifier).is_some() {
+                    self.kick(connection_identifier, disconnect.reason);
+                }
             }
             Packet::KeepAlive(_) => {}
-            Packet::Data(data) => {}
-            _ => {
-                panic!("server got weird packet: {packet:#?}");
+            Packet::Data(_) => {}
+            Packet::Ping(ping) => {
+                if self.get_connection(connection_identifier).is_some() {
+                    self.send_pong_packet(connection_identifier, ping.ping_id);
+                }
+            }
+            Packet::Pong(pong) => {
+                let now = self.clock.now();
+                if let Some(connection) = self.get_connection(connection_identifier) {
+                    connection.stats.pong_received(pong.ping_id, now);
+                }
+            }
+            Packet::MtuProbe(mtu_probe) => {
+                // Only ack probes that made it through whole
+                if mtu_probe.size as usize == size
+                    && self.get_connection(connection_identifier).is_some()
+                {
+                    self.send_mtu_probe_ack_packet(connection_identifier, mtu_probe.size);
+                }
+            }
+            Packet::MtuProbeAck(mtu_probe_ack) => {
+                if let Some(connection) = self.get_connection(connection_identifier) {
+                    if let Some(mtu_discovery) = &mut connection.mtu_discovery {
+                        mtu_discovery.ack_received(mtu_probe_ack.size);
+                    }
+                }
+            }
+            // Only ever sent by the server, or not expected from this client right now
+            Packet::ChallengeRequest(_)
+            | Packet::Queued(_)
+            | Packet::PathChallenge(_)
+            | Packet::PathResponse(_) => {
+                if let Some(connection) = self.get_connection(connection_identifier) {
+                    connection.stats.packet_dropped();
+                }
             }
         };
     }
 
-    fn handle_connection_request(&mut self, connection_identifier: ConnectionIdentifier) {
-        if self
-            .find_client_index_by_connection_identifier(connection_identifier)
-            .is_some()
-        {
-            // Already connected, just ignore
+    /// Applies the spam policy to a packet from a known connection, returns false if it shouldn't
+    /// be handled right now.
+    fn check_spam(
+        &mut self,
+        connection_identifier: ConnectionIdentifier,
+        packet: Packet,
+        size: usize,
+    ) -> bool {
+        let Some(policy) = self.config.spam_policy else {
+            return true;
+        };
+        let tps = self.config.tps;
+        let grace_period = Tick::from_duration(policy.grace_period, tps);
+        let now = self.global_tick;
+
+        let Some(connection) = self.get_connection(connection_identifier) else {
+            return true;
+        };
+        if connection.ticks_since_created < grace_period {
+            return true;
+        }
+        let Some(spam_filter) = &mut connection.spam_filter else {
+            return true;
+        };
+
+        if policy.action == SpamAction::Throttle {
+            // Anything already waiting goes first, so packets stay in order
+            if spam_filter.throttled.is_empty() && spam_filter.allow(size, now) {
+                return true;
+            }
+            if spam_filter.throttled.len() < policy.packet_burst as usize {
+                spam_filter.throttled.push_back((packet, size));
+            } else {
+                connection.stats.packet_dropped();
+            }
+            return false;
+        }
+
+        if spam_filter.allow(size, now) {
+            return true;
+        }
+
+        match policy.action {
+            SpamAction::Drop => {
+                connection.stats.packet_dropped();
+                false
+            }
+            SpamAction::Warn => {
+                // At most once a second per connection
+                let warn = spam_filter
+                    .last_warning
+                    .is_none_or(|last_warning| now.value - last_warning.value >= tps);
+                if warn {
+                    spam_filter.last_warning = Some(now);
+                    self.events.push_back(ServerEvent::Spamming {
+                        connection_identifier,
+                    });
+                }
+                true
+            }
+            SpamAction::Kick => {
+                self.kick(connection_identifier, DisconnectReason::Spam);
+                false
+            }
+            SpamAction::Throttle => unreachable!(),
+        }
+    }
+
+    /// Handles throttled packets the spam policy lets through again.
+    fn release_throttled_packets(&mut self) {
+        let now = self.global_tick;
+        for index in 0..self.connections.len() {
+            while let Some(connection) = &mut self.connections[index] {
+                let connection_identifier = connection.connection_identifier;
+                let Some(spam_filter) = &mut connection.spam_filter else {
+                    break;
+                };
+                let Some(&(packet, size)) = spam_filter.throttled.front() else {
+                    break;
+                };
+                if !spam_filter.allow(size, now) {
+                    break;
+                }
+
+                spam_filter.throttled.pop_front();
+                self.process_packet(packet, connection_identifier.addr, size);
+            }
+        }
+    }
+
+    /// A known client is talking to us from a new address. Before following it there, make sure
+    /// the new address is reachable and the client knows the connection's secret.
+    fn send_path_challenge(&mut self, connection_identifier: ConnectionIdentifier) {
+        let Some(&index) = self.connection_ids.get(&connection_identifier.id) else {
+            return;
+        };
+        let Some(connection) = &mut self.connections[index] else {
+            return;
+        };
+
+        let new_addr = connection_identifier.addr;
+        let challenge = match connection.path_challenge {
+            Some((addr, challenge)) if addr == new_addr => challenge,
+            _ => rand::random(),
+        };
+        connection.path_challenge = Some((new_addr, challenge));
+
+        let mut packet =
+            Packet::PathChallenge(PathChallenge::new(connection_identifier.id, challenge));
+        packet.set_sequence(connection.sequence);
+        connection.sequence += 1;
+        if self.config.send_debug {
+            send_dbg(packet, Some(connection_identifier), Some(index));
+        }
+        // Not through send_packet_to(), this address isn't the connection's yet
+        self.send_to(&packet.as_bytes(), new_addr).unwrap();
+    }
+
+    fn handle_path_response(&mut self, path_response: PathResponse, from: SocketAddr) {
+        let id = path_response.header.client_id;
+        let Some(&index) = self.connection_ids.get(&id) else {
+            return;
+        };
+        let Some(connection) = &mut self.connections[index] else {
+            return;
+        };
+
+        let from = ConnectionIdentifier::new(id, from).addr;
+        let valid = connection.path_challenge == Some((from, path_response.challenge))
+            && path_response.proves(connection.secret);
+        if !valid {
             return;
         }
 
-        let Some(index) = self.find_vacant_space() else {
+        let old_connection_identifier = connection.connection_identifier;
+        let old_client_identifier = connection.client_identifier();
+        let new_connection_identifier = ConnectionIdentifier::new(id, from);
+        connection.connection_identifier = new_connection_identifier;
+        connection.path_challenge = None;
+        connection.reset_timeout();
+        let new_client_identifier = connection.client_identifier();
+
+        self.connection_slots.remove(&old_connection_identifier);
+        self.connection_slots
+            .insert(new_connection_identifier, index);
+        self.client_slots.remove(&old_client_identifier);
+        self.client_slots.insert(new_client_identifier, index);
+        client_migrate_dbg(old_connection_identifier, new_connection_identifier, index);
+        self.events.push_back(ServerEvent::ConnectionMigrated {
+            from: old_connection_identifier,
+            to: new_connection_identifier,
+        });
+    }
+
+    /// Nothing is stored until the challenge is answered, the cookie we hand out carries
+    /// everything needed to accept the client later.
+    fn handle_connection_request(
+        &mut self,
+        connection_identifier: ConnectionIdentifier,
+        priority: u8,
+    ) {
+        if self.shutting_down {
+            self.send_disconnect_packet(connection_identifier, DisconnectReason::ServerShutdown);
+            return;
+        }
+
+        if self.find_accepted(connection_identifier).is_some() {
+            // It lost its session, e.g. restarted before we timed it out. The old session is
+            // replaced once the challenge is answered
+            self.send_challenge_packet(connection_identifier, priority);
+            return;
+        }
+
+        if self.send_queue_position(connection_identifier) {
+            return;
+        }
+
+        // Only clients that answer the challenge get to wait in line
+        let can_wait = self
+            .waiting_queue
+            .as_ref()
+            .is_some_and(|waiting_queue| waiting_queue.len() < waiting_queue.config.max_length);
+        let vetted_priority = self.vetted_priority(priority);
+        let can_evict = self.find_eviction(vetted_priority).is_some();
+        if !self.has_room(vetted_priority) && !can_wait && !can_evict {
             self.send_disconnect_packet(connection_identifier, DisconnectReason::ServerFull);
             return;
+        }
+
+        self.send_challenge_packet(connection_identifier, priority);
+    }
+
+    fn accept_connection(
+        &mut self,
+        connection_identifier: ConnectionIdentifier,
+        challenge_response: ChallengeResponse,
+    ) {
+        if self.shutting_down {
+            self.send_disconnect_packet(connection_identifier, DisconnectReason::ServerShutdown);
+            return;
+        }
+
+        let stale_session = self.find_accepted(connection_identifier);
+        if let Some(session) = stale_session {
+            let secret = challenge_response.secret;
+            if self
+                .get_connection(session)
+                .is_some_and(|connection| connection.secret == secret)
+            {
+                // Our KeepAlive got lost, the client is still waiting for it
+                self.send_keep_alive_packet(session);
+                return;
+            }
+        }
+
+        if self.send_queue_position(connection_identifier) {
+            return;
+        }
+
+        let valid = self.cookie_key.verify(
+            challenge_response.cookie,
+            connection_identifier.id,
+            connection_identifier.addr,
+            challenge_response.priority,
+            self.global_tick,
+            self.config.handshake_timeout,
+        );
+        let priority = challenge_response.priority;
+        if !valid || !self.used_cookies.insert(challenge_response.cookie) {
+            // Expired, forged or replayed, a real client will answer a fresh challenge
+            self.send_challenge_packet(connection_identifier, priority);
+            return;
+        }
+
+        // Same client, new handshake, so it gave up on the old session
+        if let Some(session) = stale_session {
+            if let Some(index) = self.find_client_index_by_connection_identifier(session) {
+                self.remove_connection(session, index);
+            }
+        }
+
+        let mut pending = PendingConnection {
+            client_identifier: connection_identifier,
+            priority,
+            user_data: challenge_response.user_data,
         };
+        if let Some(accept_hook) = &mut self.accept_hook {
+            if let Err(reason) = accept_hook.accept(&pending) {
+                self.send_disconnect_packet(connection_identifier, reason);
+                return;
+            }
+        }
+
+        pending.priority = self.vetted_priority(priority);
+        let priority = pending.priority;
+        if self.find_slot(priority).is_none() {
+            if let Some(evicted) = self.find_eviction(priority) {
+                self.kick(evicted, DisconnectReason::Evicted);
+                self.events.push_back(ServerEvent::Evicted {
+                    connection_identifier: evicted,
+                });
+            }
+        }
 
+        let secret = challenge_response.secret;
+        match self.find_slot(priority) {
+            Some(index) if self.has_room(priority) => {
+                self.create_connection(pending, secret, index);
+            }
+            _ => {
+                let now = self.global_tick;
+                let position = self
+                    .waiting_queue
+                    .as_mut()
+                    .and_then(|waiting_queue| waiting_queue.push(pending, secret, now));
+                match position {
+                    Some(position) => self.send_queued_packet(connection_identifier, position),
+                    None => {
+                        self.send_disconnect_packet(
+                            connection_identifier,
+                            DisconnectReason::ServerFull,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Gives a slot to the client that answered our challenge, `pending.client_identifier` has
+    /// the id it picked for itself.
+    fn create_connection(&mut self, pending: PendingConnection, secret: u64, index: usize) {
+        let client_identifier = pending.client_identifier;
+        let connection_identifier =
+            ConnectionIdentifier::new(self.new_session_id(), client_identifier.addr);
         let mut connection = Connection::new(connection_identifier);
+        connection.client_id = client_identifier.id;
         connection.client_connection_timeout = self.config.client_connection_timeout;
+        connection.secret = secret;
+        connection.priority = pending.priority;
+        connection.user_data = pending.user_data;
+        connection.stats = StatsTracker::new(self.config.ping_frequency, self.config.tps);
+        connection.congestion = self
+            .config
+            .congestion_control
+            .map(|congestion_config| CongestionController::new(congestion_config, self.config.tps));
+        connection.mtu_discovery = self
+            .config
+            .mtu_discovery
+            .map(|mtu_config| MtuDiscovery::new(mtu_config, self.config.tps));
+        connection.spam_filter = self
+            .config
+            .spam_policy
+            .map(|policy| SpamFilter::new(policy, self.config.tps, self.global_tick));
         connection.index = index;
+        if self.is_priority(pending.priority) {
+            self.priority_connections += 1;
+        }
+        self.free_slots.pop();
+        self.connection_slots.insert(connection_identifier, index);
+        self.client_slots.insert(client_identifier, index);
+        self.connection_ids
+            .entry(connection_identifier.id)
+            .or_insert(index);
         self.connections[index] = Some(connection);
 
-        self.send_challenge_packet(connection_identifier);
+        client_connect_dbg(connection_identifier, index);
+        self.send_keep_alive_packet(connection_identifier);
     }
 
-    fn accept_connection(&mut self, connection_identifier: ConnectionIdentifier) {
-        if let Some(connection) = self.get_connection(connection_identifier) {
-            connection.connected = true;
-            client_connect_dbg(connection_identifier, connection.index);
-            self.send_keep_alive_packet(connection_identifier)
+    /// True if there's a slot for a client with `priority`, and nobody with at least that
+    /// priority is waiting in line for it.
+    fn has_room(&self, priority: u8) -> bool {
+        self.find_slot(priority).is_some()
+            && self.waiting_queue.as_ref().is_none_or(|waiting_queue| {
+                !waiting_queue
+                    .clients()
+                    .any(|client| client.priority >= priority)
+            })
+    }
+
+    /// A vacant slot the client can take, regular clients can't take the reserved ones.
+    fn find_slot(&self, priority: u8) -> Option<usize> {
+        let index = self.find_vacant_space()?;
+        let Some(reserved_slots) = self.config.reserved_slots else {
+            return Some(index);
+        };
+
+        if reserved_slots.is_priority(priority) {
+            return Some(index);
+        }
+
+        let regular_connections = self.connection_count() - self.priority_connections;
+        if regular_connections + reserved_slots.slots >= self.max_connections() {
+            return None;
+        }
+
+        Some(index)
+    }
+
+    /// The connection to kick for a priority client when we're full: the lowest priority one,
+    /// the newest of those if there's a tie. None if eviction is off or nobody ranks lower.
+    fn find_eviction(&self, priority: u8) -> Option<ConnectionIdentifier> {
+        let reserved_slots = self.config.reserved_slots?;
+        if !reserved_slots.evict || !reserved_slots.is_priority(priority) {
+            return None;
+        }
+
+        self.connections
+            .iter()
+            .flatten()
+            .filter(|connection| connection.priority < priority)
+            .min_by(|a, b| {
+                a.priority.cmp(&b.priority).then(
+                    a.ticks_since_created
+                        .value
+                        .total_cmp(&b.ticks_since_created.value),
+                )
+            })
+            .map(|connection| connection.connection_identifier)
+    }
+
+    /// The priority a client gets for the one it claimed. Anyone can claim a high priority, so
+    /// it only counts once the application had a chance to vet it in its AcceptHook.
+    fn vetted_priority(&self, priority: u8) -> u8 {
+        if self.accept_hook.is_some() {
+            priority
         } else {
-            panic!("Either this is a bug, or someone is sending packets out of order.")
+            0
+        }
+    }
+
+    fn is_priority(&self, priority: u8) -> bool {
+        self.config
+            .reserved_slots
+            .is_some_and(|reserved_slots| reserved_slots.is_priority(priority))
+    }
+
+    /// Reminds a waiting client of its place in line, returns false if it isn't waiting.
+    fn send_queue_position(&mut self, client_identifier: ConnectionIdentifier) -> bool {
+        let now = self.global_tick;
+        let Some(position) = self
+            .waiting_queue
+            .as_mut()
+            .and_then(|waiting_queue| waiting_queue.heard_from(client_identifier, now))
+        else {
+            return false;
+        };
+
+        self.send_queued_packet(client_identifier, position);
+        true
+    }
+
+    /// Keep-alives and disconnects from clients waiting in line, returns false if `packet` still
+    /// needs handling.
+    fn handle_waiting_packet(
+        &mut self,
+        packet: Packet,
+        client_identifier: ConnectionIdentifier,
+    ) -> bool {
+        let now = self.global_tick;
+        let Some(waiting_queue) = &mut self.waiting_queue else {
+            return false;
+        };
+
+        match packet {
+            Packet::ConnectionRequest(_) | Packet::ChallengeResponse(_) => false,
+            Packet::Disconnect(_) => waiting_queue.remove(client_identifier),
+            _ => waiting_queue.heard_from(client_identifier, now).is_some(),
+        }
+    }
+
+    fn update_waiting_queue(&mut self) {
+        let now = self.global_tick;
+        let idle_timeout = self.config.client_connection_timeout;
+        let Some(waiting_queue) = &mut self.waiting_queue else {
+            return;
+        };
+
+        for client in waiting_queue.remove_timed_out(now, idle_timeout) {
+            self.send_disconnect_packet(client.client_identifier, DisconnectReason::Timeout);
+        }
+
+        // First come, first served, but a regular client at the front doesn't hold up priority
+        // clients behind it that can use a reserved slot
+        let Some(mut waiting_queue) = self.waiting_queue.take() else {
+            return;
+        };
+        while let Some(client) =
+            waiting_queue.pop_first(|client| self.find_slot(client.priority).is_some())
+        {
+            let index = self.find_slot(client.priority).unwrap();
+            self.create_connection(client.pending(), client.secret, index);
+        }
+        self.waiting_queue = Some(waiting_queue);
+
+        let Some(waiting_queue) = &self.waiting_queue else {
+            return;
+        };
+        if waiting_queue.is_update_due(now) {
+            let positions: Vec<_> = waiting_queue
+                .clients()
+                .enumerate()
+                .map(|(index, client)| (client.client_identifier, index + 1))
+                .collect();
+            for (client_identifier, position) in positions {
+                self.send_queued_packet(client_identifier, position);
+            }
+        }
+    }
+
+    /// Number of clients waiting for a slot.
+    pub fn waiting_count(&self) -> usize {
+        self.waiting_queue.as_ref().map_or(0, WaitingQueue::len)
+    }
+
+    /// Session of an accepted client, looked up by the id it picked for itself.
+    fn find_accepted(
+        &self,
+        client_identifier: ConnectionIdentifier,
+    ) -> Option<ConnectionIdentifier> {
+        let &index = self.client_slots.get(&client_identifier)?;
+        Some(self.connections[index].as_ref()?.connection_identifier)
+    }
+
+    fn new_session_id(&self) -> UnetId {
+        loop {
+            let session_id = UnetId::new();
+            if !self.connection_ids.contains_key(&session_id) {
+                return session_id;
+            }
         }
     }
 
@@ -222,36 +1059,90 @@ impl UnetServer {
         &self,
         connection_identifier: ConnectionIdentifier,
     ) -> Option<usize> {
-        let id = connection_identifier.id;
-        let addr = connection_identifier.addr;
-        let mut index = None;
-        for (idx, connection) in self.connections.iter().enumerate() {
-            if let Some(connection) = connection {
-                let identifier = connection.connection_identifier;
-                if identifier.id == id && identifier.addr == addr {
-                    index = Some(idx);
-                    break;
-                }
-            }
+        self.connection_slots.get(&connection_identifier).copied()
+    }
+
+    fn find_vacant_space(&self) -> Option<usize> {
+        if self.connection_count() >= self.max_connections() {
+            return None;
         }
 
-        index
+        self.free_slots.last().copied()
     }
 
-    fn find_vacant_space(&self) -> Option<usize> {
-        let mut index = None;
-        for (idx, connection) in self.connections.iter().enumerate() {
-            if connection.is_none() {
-                index = Some(idx);
-                break;
+    pub fn stats(&self, connection_identifier: ConnectionIdentifier) -> Option<NetworkStats> {
+        let index = self.find_client_index_by_connection_identifier(connection_identifier)?;
+        let connection = self.connections[index].as_ref()?;
+        Some(connection.stats())
+    }
+
+    pub fn allowed_send_rate(&self, connection_identifier: ConnectionIdentifier) -> Option<f32> {
+        let index = self.find_client_index_by_connection_identifier(connection_identifier)?;
+        self.connections[index].as_ref()?.allowed_send_rate()
+    }
+
+    /// Largest packet that's known to reach this connection, see `Connection::mtu()`.
+    pub fn mtu(&self, connection_identifier: ConnectionIdentifier) -> Option<usize> {
+        let index = self.find_client_index_by_connection_identifier(connection_identifier)?;
+        Some(self.connections[index].as_ref()?.mtu())
+    }
+
+    pub fn connection_count(&self) -> usize {
+        self.connection_slots.len()
+    }
+
+    pub fn max_connections(&self) -> usize {
+        self.config.max_connections
+    }
+
+    /// The new limit only applies to new connections, unless `drain` is set, in which case the
+    /// connections over the limit are kicked, starting from the highest slot.
+    pub fn set_max_connections(&mut self, max_connections: usize, drain: bool) {
+        self.config.max_connections = max_connections;
+
+        if drain {
+            let mut index = self.connections.len();
+            while self.connection_count() > max_connections && index > 0 {
+                index -= 1;
+                if let Some(connection) = &self.connections[index] {
+                    self.kick(
+                        connection.connection_identifier,
+                        DisconnectReason::ServerFull,
+                    );
+                }
             }
         }
 
-        index
+        if self.connections.len() < max_connections {
+            self.connections.resize(max_connections, None);
+        }
+        while self.connections.len() > max_connections
+            && matches!(self.connections.last(), Some(None))
+        {
+            self.connections.pop();
+        }
+
+        self.free_slots = (0..max_connections)
+            .rev()
+            .filter(|&index| self.connections[index].is_none())
+            .collect();
     }
 
-    fn send_challenge_packet(&mut self, connection_identifier: ConnectionIdentifier) {
-        let packet = Packet::ChallengeRequest;
+    fn send_challenge_packet(&mut self, connection_identifier: ConnectionIdentifier, priority: u8) {
+        let client_id = connection_identifier.id;
+        let cookie = self.cookie_key.issue(
+            client_id,
+            connection_identifier.addr,
+            priority,
+            self.global_tick,
+        );
+        let packet = Packet::ChallengeRequest(ChallengeRequest::new(client_id, cookie));
+        self.send_packet_to(packet, connection_identifier).unwrap();
+    }
+
+    fn send_queued_packet(&mut self, connection_identifier: ConnectionIdentifier, position: usize) {
+        let client_id = connection_identifier.id;
+        let packet = Packet::Queued(Queued::new(client_id, position as u32));
         self.send_packet_to(packet, connection_identifier).unwrap();
     }
 
@@ -261,6 +1152,34 @@ impl UnetServer {
         self.send_packet_to(packet, connection_identifier).unwrap();
     }
 
+    fn send_ping_packet(&mut self, connection_identifier: ConnectionIdentifier, ping_id: u64) {
+        let client_id = connection_identifier.id;
+        let packet = Packet::Ping(Ping::new(client_id, ping_id));
+        self.send_packet_to(packet, connection_identifier).unwrap();
+    }
+
+    fn send_pong_packet(&mut self, connection_identifier: ConnectionIdentifier, ping_id: u64) {
+        let client_id = connection_identifier.id;
+        let packet = Packet::Pong(Pong::new(client_id, ping_id));
+        self.send_packet_to(packet, connection_identifier).unwrap();
+    }
+
+    fn send_mtu_probe_packet(&mut self, connection_identifier: ConnectionIdentifier, size: u16) {
+        let client_id = connection_identifier.id;
+        let packet = Packet::MtuProbe(MtuProbe::new(client_id, size));
+        self.send_packet_to(packet, connection_identifier).unwrap();
+    }
+
+    fn send_mtu_probe_ack_packet(
+        &mut self,
+        connection_identifier: ConnectionIdentifier,
+        size: u16,
+    ) {
+        let client_id = connection_identifier.id;
+        let packet = Packet::MtuProbeAck(MtuProbeAck::new(client_id, size));
+        self.send_packet_to(packet, connection_identifier).unwrap();
+    }
+
     fn send_disconnect_packet(
         &mut self,
         connection_identifier: ConnectionIdentifier,
@@ -285,51 +1204,67 @@ impl UnetServer {
     }
 
     fn kick_timed_out_connections(&mut self) {
-        for connection in self.connections.clone().into_iter().flatten() {
+        for index in 0..self.connections.len() {
+            let Some(connection) = &self.connections[index] else {
+                continue;
+            };
+
             if connection.timed_out() {
                 self.kick(connection.connection_identifier, DisconnectReason::Timeout)
             }
         }
     }
 
-    fn kick_spamming_connections(&mut self) {
-        if let Some(max_rolling_packets_per_tick) = self.config.max_rolling_packets_per_tick {
-            for connection in &mut self.connections.clone().iter().flatten() {
-                if connection.is_spamming(max_rolling_packets_per_tick) {
-                    self.kick(connection.connection_identifier, DisconnectReason::Spam)
-                }
-            }
-        }
-    }
-
     fn kick(&mut self, connection_identifier: ConnectionIdentifier, reason: DisconnectReason) {
         if let Some(index) = self.find_client_index_by_connection_identifier(connection_identifier)
         {
-            if let Some(connection) = self.connections[index].take() {
-                client_disconnect_dbg(connection_identifier, index);
-                self.send_disconnect_packet(connection.connection_identifier, reason)
+            if self.connections[index].is_some() {
+                // Sent first, so it still gets the connection's sequence
+                self.send_disconnect_packet(connection_identifier, reason);
+                self.remove_connection(connection_identifier, index);
             }
         } else {
             panic!("Just tried kicking a connection that doesn't exist? {connection_identifier:#?}")
         }
     }
 
-    fn tick_connections(&mut self) {
-        for connection in self.connections.iter_mut().flatten() {
-            connection.ticks_since_last_packet_sent.value += 1.0;
-            connection.ticks_since_last_packet_received.value += 1.0;
-            connection
-                .rolling_packets_per_tick_received
-                .add(connection.packets_per_tick_received);
+    fn remove_connection(&mut self, connection_identifier: ConnectionIdentifier, index: usize) {
+        if let Some(connection) = self.connections[index].take() {
+            let client_identifier = connection.client_identifier();
+            if self.client_slots.get(&client_identifier) == Some(&index) {
+                self.client_slots.remove(&client_identifier);
+            }
+            if self.is_priority(connection.priority) {
+                self.priority_connections -= 1;
+            }
         }
+        self.connection_slots.remove(&connection_identifier);
+        if self.connection_ids.get(&connection_identifier.id) == Some(&index) {
+            self.connection_ids.remove(&connection_identifier.id);
+        }
+        if index < self.max_connections() {
+            self.free_slots.push(index);
+        }
+        client_disconnect_dbg(connection_identifier, index);
     }
 
-    fn reset_connections_stats(&mut self) {
+    fn tick_connections(&mut self) {
+        let now = self.clock.now();
         for connection in self.connections.iter_mut().flatten() {
-            connection.packets_per_tick_received = 0.0;
+            connection.stats.tick(now);
+            if let Some(congestion) = &mut connection.congestion {
+                congestion.tick(&connection.stats.stats());
+            }
+            if let Some(mtu_discovery) = &mut connection.mtu_discovery {
+                mtu_discovery.tick();
+            }
+            connection.ticks_since_last_packet_sent.value += 1.0;
+            connection.ticks_since_last_packet_received.value += 1.0;
+            connection.ticks_since_created.value += 1.0;
         }
     }
 
+    #[allow(dead_code)]
     fn print_state(&self) {
         for connection in self.connections.iter().flatten() {
             connection_state_dbg(connection);
@@ -338,13 +1273,22 @@ impl UnetServer {
 }
 
 pub fn server_starting_dbg(server: &UnetServer) {
+    let addrs = server
+        .config
+        .listen_addrs()
+        .iter()
+        .map(|addr| addr.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let dual_stack = if server.config.dual_stack {
+        " (dual-stack)"
+    } else {
+        ""
+    };
     println!(
-        "Starting server on {}",
-        server
-            .config
-            .addr
-            .to_string()
-            .truecolor(YELLOW.r, YELLOW.g, YELLOW.b),
+        "Starting server on {}{}",
+        addrs.truecolor(YELLOW.r, YELLOW.g, YELLOW.b),
+        dual_stack
     );
 }
 
@@ -352,14 +1296,14 @@ pub fn connection_state_dbg(connection: &Connection) {
     println!(
         r#"{:>16x}:     ticks_since_last_packet_received:   {:?}
                       ticks_since_last_packet_sent:       {:?}
-                      packets_per_tick_received:          {:?}
-                      rolling_packets_per_tick_received:  {:?}
+                      ticks_since_created:                {:?}
+                      stats:                              {:?}
                 "#,
         connection.connection_identifier.id.0,
         connection.ticks_since_last_packet_received,
         connection.ticks_since_last_packet_sent,
-        connection.packets_per_tick_received,
-        connection.rolling_packets_per_tick_received.value(),
+        connection.ticks_since_created,
+        connection.stats(),
     );
 }
 
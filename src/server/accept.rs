@@ -0,0 +1,36 @@
+use crate::packet::disconnect::DisconnectReason;
+use crate::server::connection::ConnectionIdentifier;
+use crate::user_data::UserData;
+use std::fmt;
+
+/// A client that answered our challenge, before it gets a slot or a place in the waiting queue.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PendingConnection {
+    pub client_identifier: ConnectionIdentifier, // Id the client picked, and its address
+    pub priority: u8, // As claimed by the client, turn it away if it shouldn't have it
+    pub user_data: UserData,
+}
+
+/// Lets the application turn clients away, e.g. ones with a bad auth ticket or an old build.
+/// `Err` is sent to the client as the reason it was disconnected.
+///
+/// Implemented for closures, so `ServerConfig::accept_hook` can be set to
+/// `Some(Box::new(|pending: &PendingConnection| ...))`.
+pub trait AcceptHook: Send {
+    fn accept(&mut self, pending: &PendingConnection) -> Result<(), DisconnectReason>;
+}
+
+impl<F> AcceptHook for F
+where
+    F: FnMut(&PendingConnection) -> Result<(), DisconnectReason> + Send,
+{
+    fn accept(&mut self, pending: &PendingConnection) -> Result<(), DisconnectReason> {
+        self(pending)
+    }
+}
+
+impl fmt::Debug for dyn AcceptHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AcceptHook")
+    }
+}
@@ -0,0 +1,355 @@
+use crate::packet::UnetId;
+use crate::server::connection::ConnectionIdentifier;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::net::IpAddr;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Who a ban (or allow list entry) applies to. Written as `id:<hex>`, `<ip>` or `<ip>/<prefix>`.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum BanTarget {
+    Id(UnetId), // The id the client picked for itself, so a client can dodge it with a new one
+    Ip(IpAddr),
+    Range(IpAddr, u8), // Network address and prefix length, e.g. 10.0.0.0/8
+}
+
+impl BanTarget {
+    pub fn matches(&self, connection_identifier: ConnectionIdentifier) -> bool {
+        let ip = connection_identifier.addr.ip().to_canonical();
+        match *self {
+            BanTarget::Id(id) => id == connection_identifier.id,
+            BanTarget::Ip(banned) => banned.to_canonical() == ip,
+            BanTarget::Range(network, prefix) => in_range(ip, network.to_canonical(), prefix),
+        }
+    }
+
+    // So IPv4-mapped addresses are found by a plain lookup
+    fn canonical(self) -> Self {
+        match self {
+            BanTarget::Ip(ip) => BanTarget::Ip(ip.to_canonical()),
+            target => target,
+        }
+    }
+}
+
+fn in_range(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX
+                .checked_shl(32 - prefix.min(32) as u32)
+                .unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX
+                .checked_shl(128 - prefix.min(128) as u32)
+                .unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+impl Display for BanTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BanTarget::Id(id) => write!(f, "id:{:x}", id.0),
+            BanTarget::Ip(ip) => write!(f, "{ip}"),
+            BanTarget::Range(network, prefix) => write!(f, "{network}/{prefix}"),
+        }
+    }
+}
+
+impl FromStr for BanTarget {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("Bad ban target: {s}"));
+
+        if let Some(id) = s.strip_prefix("id:") {
+            let id = u64::from_str_radix(id, 16).map_err(|_| invalid())?;
+            return Ok(BanTarget::Id(UnetId(id)));
+        }
+
+        if let Some((network, prefix)) = s.split_once('/') {
+            let network: IpAddr = network.parse().map_err(|_| invalid())?;
+            let prefix: u8 = prefix.parse().map_err(|_| invalid())?;
+            let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+            if prefix > max_prefix {
+                return Err(invalid());
+            }
+            return Ok(BanTarget::Range(network, prefix));
+        }
+
+        Ok(BanTarget::Ip(s.parse().map_err(|_| invalid())?))
+    }
+}
+
+/// Bans with optional expiry, plus an allow list. In `allow_list_only` mode everyone that isn't
+/// on the allow list counts as banned too.
+///
+/// Saved as one entry per line: `ban <target> [<expiry as unix seconds>]`, `allow <target>` and
+/// `allow-list-only`. Empty lines and lines starting with `#` are ignored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BanList {
+    bans: HashMap<BanTarget, Option<SystemTime>>, // Ids and IPs, None never expires
+    range_bans: HashMap<BanTarget, Option<SystemTime>>, // Have to be checked one by one
+    allowed: HashSet<BanTarget>,
+    allowed_ranges: HashSet<BanTarget>,
+    pub allow_list_only: bool,
+}
+
+impl BanList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ban(&mut self, target: BanTarget, expires_at: Option<SystemTime>) {
+        self.bans_for(target).insert(target.canonical(), expires_at);
+    }
+
+    pub fn unban(&mut self, target: BanTarget) -> bool {
+        self.bans_for(target).remove(&target.canonical()).is_some()
+    }
+
+    pub fn allow(&mut self, target: BanTarget) {
+        self.allowed_for(target).insert(target.canonical());
+    }
+
+    pub fn disallow(&mut self, target: BanTarget) -> bool {
+        self.allowed_for(target).remove(&target.canonical())
+    }
+
+    pub fn bans(&self) -> impl Iterator<Item = (BanTarget, Option<SystemTime>)> + '_ {
+        self.bans
+            .iter()
+            .chain(&self.range_bans)
+            .map(|(target, expires_at)| (*target, *expires_at))
+    }
+
+    pub fn allowed(&self) -> impl Iterator<Item = BanTarget> + '_ {
+        self.allowed.iter().chain(&self.allowed_ranges).copied()
+    }
+
+    /// Called for every packet, so only the ranges are checked one by one.
+    pub fn is_banned(&self, connection_identifier: ConnectionIdentifier, now: SystemTime) -> bool {
+        let active =
+            |expires_at: &Option<SystemTime>| expires_at.is_none_or(|expires_at| now < expires_at);
+        let exact = [
+            BanTarget::Id(connection_identifier.id),
+            BanTarget::Ip(connection_identifier.addr.ip().to_canonical()),
+        ];
+
+        let banned = exact
+            .iter()
+            .any(|target| self.bans.get(target).is_some_and(active))
+            || self.range_bans.iter().any(|(target, expires_at)| {
+                active(expires_at) && target.matches(connection_identifier)
+            });
+        if banned {
+            return true;
+        }
+
+        self.allow_list_only
+            && !exact.iter().any(|target| self.allowed.contains(target))
+            && !self
+                .allowed_ranges
+                .iter()
+                .any(|target| target.matches(connection_identifier))
+    }
+
+    pub fn remove_expired(&mut self, now: SystemTime) {
+        let active = |_: &BanTarget, expires_at: &mut Option<SystemTime>| {
+            expires_at.is_none_or(|expires_at| now < expires_at)
+        };
+        self.bans.retain(active);
+        self.range_bans.retain(active);
+    }
+
+    fn bans_for(&mut self, target: BanTarget) -> &mut HashMap<BanTarget, Option<SystemTime>> {
+        match target {
+            BanTarget::Range(..) => &mut self.range_bans,
+            _ => &mut self.bans,
+        }
+    }
+
+    fn allowed_for(&mut self, target: BanTarget) -> &mut HashSet<BanTarget> {
+        match target {
+            BanTarget::Range(..) => &mut self.allowed_ranges,
+            _ => &mut self.allowed,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?.parse()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl Display for BanList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.allow_list_only {
+            writeln!(f, "allow-list-only")?;
+        }
+
+        // Sorted, so saving the same list twice gives the same file
+        let mut bans = self
+            .bans()
+            .map(|(target, expires_at)| match expires_at {
+                Some(expires_at) => {
+                    let expires_at = expires_at.duration_since(UNIX_EPOCH).unwrap_or_default();
+                    format!("ban {target} {}", expires_at.as_secs())
+                }
+                None => format!("ban {target}"),
+            })
+            .collect::<Vec<_>>();
+        bans.sort();
+
+        let mut allowed = self
+            .allowed()
+            .map(|target| format!("allow {target}"))
+            .collect::<Vec<_>>();
+        allowed.sort();
+
+        for line in bans.iter().chain(&allowed) {
+            writeln!(f, "{line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for BanList {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ban_list = BanList::new();
+
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Bad ban list line: {line}"),
+                )
+            };
+            let words = line.split_whitespace().collect::<Vec<_>>();
+            match words.as_slice() {
+                ["allow-list-only"] => ban_list.allow_list_only = true,
+                ["ban", target] => ban_list.ban(target.parse()?, None),
+                ["ban", target, expires_at] => {
+                    let expires_at: u64 = expires_at.parse().map_err(|_| invalid())?;
+                    let expires_at = UNIX_EPOCH + Duration::from_secs(expires_at);
+                    ban_list.ban(target.parse()?, Some(expires_at));
+                }
+                ["allow", target] => ban_list.allow(target.parse()?),
+                _ => return Err(invalid()),
+            }
+        }
+
+        Ok(ban_list)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packet::UnetId;
+    use crate::server::ban::{BanList, BanTarget};
+    use crate::server::connection::ConnectionIdentifier;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn ci(id: u64, addr: &str) -> ConnectionIdentifier {
+        ConnectionIdentifier::new(UnetId(id), addr.parse().unwrap())
+    }
+
+    #[test]
+    fn targets() {
+        let now = SystemTime::now();
+        let mut ban_list = BanList::new();
+        ban_list.ban("id:ff".parse().unwrap(), None);
+        ban_list.ban("10.0.0.1".parse().unwrap(), None);
+        ban_list.ban("192.168.0.0/16".parse().unwrap(), None);
+        ban_list.ban("2001:db8::/32".parse().unwrap(), None);
+
+        assert!(ban_list.is_banned(ci(0xff, "1.1.1.1:80"), now));
+        assert!(ban_list.is_banned(ci(1, "10.0.0.1:80"), now));
+        assert!(ban_list.is_banned(ci(1, "[::ffff:10.0.0.1]:80"), now));
+        assert!(ban_list.is_banned(ci(1, "192.168.44.3:80"), now));
+        assert!(ban_list.is_banned(ci(1, "[2001:db8::1]:80"), now));
+        assert!(!ban_list.is_banned(ci(1, "10.0.0.2:80"), now));
+        assert!(!ban_list.is_banned(ci(1, "192.169.0.1:80"), now));
+    }
+
+    #[test]
+    fn mapped_addresses() {
+        let now = SystemTime::now();
+        let mut ban_list = BanList::new();
+        ban_list.ban("::ffff:10.0.0.1".parse().unwrap(), None);
+        assert!(ban_list.is_banned(ci(1, "10.0.0.1:80"), now));
+        assert!(ban_list.unban("10.0.0.1".parse().unwrap()));
+        assert!(!ban_list.is_banned(ci(1, "[::ffff:10.0.0.1]:80"), now));
+    }
+
+    #[test]
+    fn expiry() {
+        let now = SystemTime::now();
+        let target = BanTarget::Id(UnetId(1));
+        let mut ban_list = BanList::new();
+        ban_list.ban(target, Some(now + Duration::from_secs(10)));
+
+        assert!(ban_list.is_banned(ci(1, "1.1.1.1:80"), now));
+        assert!(!ban_list.is_banned(ci(1, "1.1.1.1:80"), now + Duration::from_secs(10)));
+
+        ban_list.remove_expired(now + Duration::from_secs(10));
+        assert_eq!(ban_list.bans().count(), 0);
+    }
+
+    #[test]
+    fn allow_list_only() {
+        let now = SystemTime::now();
+        let mut ban_list = BanList::new();
+        ban_list.allow_list_only = true;
+        ban_list.allow("127.0.0.0/8".parse().unwrap());
+
+        assert!(!ban_list.is_banned(ci(1, "127.0.0.1:80"), now));
+        assert!(ban_list.is_banned(ci(1, "10.0.0.1:80"), now));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut ban_list = BanList::new();
+        ban_list.allow_list_only = true;
+        ban_list.ban("id:deadbeef".parse().unwrap(), None);
+        ban_list.ban(
+            "10.0.0.0/8".parse().unwrap(),
+            Some(UNIX_EPOCH + Duration::from_secs(2000000000)),
+        );
+        ban_list.allow("::1".parse().unwrap());
+
+        let text = ban_list.to_string();
+        assert_eq!(
+            text,
+            "allow-list-only\nban 10.0.0.0/8 2000000000\nban id:deadbeef\nallow ::1\n"
+        );
+        assert_eq!(text.parse::<BanList>().unwrap(), ban_list);
+    }
+
+    #[test]
+    fn bad_lines() {
+        assert!("ban".parse::<BanList>().is_err());
+        assert!("ban 10.0.0.0/33".parse::<BanList>().is_err());
+        assert!("kick 10.0.0.1".parse::<BanList>().is_err());
+        assert!("# comment\n\nban 10.0.0.1".parse::<BanList>().is_ok());
+    }
+}
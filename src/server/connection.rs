@@ -1,47 +1,98 @@
+use crate::congestion::CongestionController;
+use crate::mtu::MtuDiscovery;
+use crate::network::udp::canonical_addr;
 use crate::packet::{Packet, UnetId};
-use crate::rolling_average::RollingAverage;
+use crate::replay_window::ReplayWindow;
+use crate::server::spam::SpamFilter;
+use crate::stats::{NetworkStats, StatsTracker};
 use crate::tick::Tick;
-use crate::{DEFAULT_CLIENT_CONNECTION_TIMEOUT, DEFAULT_KEEP_ALIVE_FREQUENCY};
+use crate::user_data::UserData;
+use crate::{
+    BUF_SIZE, DEFAULT_CLIENT_CONNECTION_TIMEOUT, DEFAULT_KEEP_ALIVE_FREQUENCY,
+    DEFAULT_PING_FREQUENCY, DEFAULT_TPS,
+};
+use std::collections::VecDeque;
 use std::net::SocketAddr;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub struct ConnectionIdentifier {
     pub id: UnetId,
     pub addr: SocketAddr,
 }
 impl ConnectionIdentifier {
     pub fn new(id: UnetId, addr: SocketAddr) -> Self {
+        let addr = canonical_addr(addr);
         Self { id, addr }
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct Connection {
-    pub connection_identifier: ConnectionIdentifier,
+    pub connection_identifier: ConnectionIdentifier, // Session id we assigned, and address
+    pub client_id: UnetId,                           // Id the client picked for itself
+    pub priority: u8,                                // From the handshake, see ReservedSlotsConfig
+    pub user_data: UserData,                         // From the handshake, see AcceptHook
     pub ticks_since_last_packet_sent: Tick,
     pub ticks_since_last_packet_received: Tick,
-    pub rolling_packets_per_tick_received: RollingAverage,
-    pub packets_per_tick_received: f32, // Packets received from Connection
-    pub packet_sequence: u64,
+    pub replay_window: ReplayWindow, // Sequences received from this client
+    pub sequence: u64,               // Sequence of the next packet we send to this client
+    pub send_queue: VecDeque<Packet>, // Flushed by `UnetServer::send_packets` once connected
     pub index: usize,
     pub client_connection_timeout: Tick,
-    pub connected: bool,
+    pub ticks_since_created: Tick, // Needed for the spam policy's grace period
+    pub stats: StatsTracker,
+    pub congestion: Option<CongestionController>,
+    pub mtu_discovery: Option<MtuDiscovery>,
+    pub spam_filter: Option<SpamFilter>,
+    pub secret: u64, // From the ChallengeResponse, needed to migrate to a new address
+    pub path_challenge: Option<(SocketAddr, u64)>, // New address being validated, and its challenge
 }
 
 impl Connection {
     pub fn new(connection_identifier: ConnectionIdentifier) -> Self {
         Self {
             connection_identifier,
+            client_id: connection_identifier.id,
+            priority: 0,
+            user_data: UserData::default(),
             ticks_since_last_packet_sent: Tick { value: 0.0 },
             ticks_since_last_packet_received: Tick { value: 0.0 },
-            rolling_packets_per_tick_received: RollingAverage::new(25),
-            packets_per_tick_received: 0.0,
-            packet_sequence: 0,
+            replay_window: ReplayWindow::new(),
+            sequence: 0,
+            send_queue: VecDeque::new(),
             index: 0,
             client_connection_timeout: DEFAULT_CLIENT_CONNECTION_TIMEOUT,
-            connected: false,
+            ticks_since_created: Tick { value: 0.0 },
+            stats: StatsTracker::new(DEFAULT_PING_FREQUENCY, DEFAULT_TPS),
+            congestion: None,
+            mtu_discovery: None,
+            spam_filter: None,
+            secret: 0,
+            path_challenge: None,
         }
     }
+
+    /// The client's own id with its address, what bans by id are matched against.
+    pub fn client_identifier(&self) -> ConnectionIdentifier {
+        ConnectionIdentifier::new(self.client_id, self.connection_identifier.addr)
+    }
+
+    pub fn stats(&self) -> NetworkStats {
+        self.stats.stats()
+    }
+
+    /// Packets per second we should be sending to this connection, None if it isn't throttled.
+    pub fn allowed_send_rate(&self) -> Option<f32> {
+        Some(self.congestion.as_ref()?.allowed_rate())
+    }
+
+    /// Largest packet we can send to this connection, `BUF_SIZE` unless discovered otherwise.
+    pub fn mtu(&self) -> usize {
+        self.mtu_discovery
+            .as_ref()
+            .map_or(BUF_SIZE, MtuDiscovery::mtu)
+    }
+
     pub fn still_alive(&mut self) {
         self.ticks_since_last_packet_sent.value = 0.0;
     }
@@ -58,12 +109,23 @@ impl Connection {
         self.ticks_since_last_packet_received >= self.client_connection_timeout
     }
 
-    pub fn is_spamming(&self, max_packets_per_tick: f32) -> bool {
-        self.rolling_packets_per_tick_received.value() >= max_packets_per_tick
+    /// Marks the packet's sequence as received, false if it's a duplicate or too old.
+    pub fn accept_sequence(&mut self, packet: Packet) -> bool {
+        let header = packet.header();
+        let check = self.replay_window.receive(header.sequence);
+        self.stats.replay_checked(check)
     }
+}
 
-    pub fn is_packet_out_of_order(&self, packet: Packet) -> bool {
-        let header = packet.header();
-        self.packet_sequence >= header.sequence
+#[cfg(test)]
+mod tests {
+    use crate::packet::UnetId;
+    use crate::server::connection::ConnectionIdentifier;
+
+    #[test]
+    fn connection_identifier_unmaps_ipv4() {
+        let mapped = ConnectionIdentifier::new(UnetId(1), "[::ffff:10.0.0.1]:80".parse().unwrap());
+        let plain = ConnectionIdentifier::new(UnetId(1), "10.0.0.1:80".parse().unwrap());
+        assert_eq!(mapped.addr, plain.addr);
     }
 }
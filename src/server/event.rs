@@ -0,0 +1,19 @@
+use crate::server::connection::ConnectionIdentifier;
+
+/// Things that happened to connections, see `UnetServer::poll_event()`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ServerEvent {
+    // The client's address changed (e.g. its NAT rebound the port) and the connection moved with it
+    ConnectionMigrated {
+        from: ConnectionIdentifier,
+        to: ConnectionIdentifier,
+    },
+    // Over the spam policy limits, with `SpamAction::Warn`. Sent at most once a second
+    Spamming {
+        connection_identifier: ConnectionIdentifier,
+    },
+    // Kicked to make room for a higher priority client, see `ReservedSlotsConfig::evict`
+    Evicted {
+        connection_identifier: ConnectionIdentifier,
+    },
+}
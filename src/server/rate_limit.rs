@@ -0,0 +1,181 @@
+use crate::tick::Tick;
+use crate::token_bucket::TokenBucket;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Limits on traffic that doesn't belong to a connection yet, per source IP. Rates are packets
+/// per second, bursts are in packets.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RateLimitConfig {
+    pub handshake_rate: f32, // ConnectionRequest and ChallengeResponse packets
+    pub handshake_burst: f32,
+    pub unknown_rate: f32, // Anything else from an address with no connection
+    pub unknown_burst: f32,
+    pub max_addresses: usize, // Tracked at once, traffic from new ones is dropped while full
+}
+
+impl RateLimitConfig {
+    pub fn new() -> Self {
+        Self {
+            handshake_rate: 20.0,
+            handshake_burst: 20.0,
+            unknown_rate: 10.0,
+            unknown_burst: 10.0,
+            max_addresses: 65536,
+        }
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct RateLimitStats {
+    pub handshake_packets_dropped: u64,
+    pub handshake_bytes_dropped: u64,
+    pub unknown_packets_dropped: u64,
+    pub unknown_bytes_dropped: u64,
+}
+
+#[derive(Clone, Debug)]
+struct Buckets {
+    handshake: TokenBucket,
+    unknown: TokenBucket,
+}
+
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    pub config: RateLimitConfig,
+    buckets: HashMap<IpAddr, Buckets>,
+    stats: RateLimitStats,
+    tps: f32,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig, tps: f32) -> Self {
+        Self {
+            config,
+            buckets: HashMap::new(),
+            stats: RateLimitStats::default(),
+            tps,
+        }
+    }
+
+    pub fn stats(&self) -> RateLimitStats {
+        self.stats
+    }
+
+    pub fn allow_handshake(&mut self, ip: IpAddr, size: usize, now: Tick) -> bool {
+        let allowed = self
+            .buckets(ip, now)
+            .is_some_and(|buckets| buckets.handshake.try_take(1.0, now));
+        if !allowed {
+            self.stats.handshake_packets_dropped += 1;
+            self.stats.handshake_bytes_dropped += size as u64;
+        }
+        allowed
+    }
+
+    pub fn allow_unknown(&mut self, ip: IpAddr, size: usize, now: Tick) -> bool {
+        let allowed = self
+            .buckets(ip, now)
+            .is_some_and(|buckets| buckets.unknown.try_take(1.0, now));
+        if !allowed {
+            self.stats.unknown_packets_dropped += 1;
+            self.stats.unknown_bytes_dropped += size as u64;
+        }
+        allowed
+    }
+
+    /// Forgets addresses whose buckets have filled back up, they'd start out full anyway.
+    pub fn prune(&mut self, now: Tick) {
+        self.buckets
+            .retain(|_, buckets| !buckets.handshake.is_full(now) || !buckets.unknown.is_full(now));
+    }
+
+    /// None if `ip` is new and we're already tracking `max_addresses`, so a flood from spoofed
+    /// addresses can't grow the map without bound.
+    fn buckets(&mut self, ip: IpAddr, now: Tick) -> Option<&mut Buckets> {
+        let config = self.config;
+        let tps = self.tps;
+        let full = self.buckets.len() >= config.max_addresses;
+        match self.buckets.entry(ip) {
+            Entry::Occupied(entry) => Some(entry.into_mut()),
+            Entry::Vacant(_) if full => None,
+            Entry::Vacant(entry) => Some(entry.insert(Buckets {
+                handshake: TokenBucket::new(
+                    config.handshake_rate,
+                    config.handshake_burst,
+                    tps,
+                    now,
+                ),
+                unknown: TokenBucket::new(config.unknown_rate, config.unknown_burst, tps, now),
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::rate_limit::{RateLimitConfig, RateLimiter};
+    use crate::tick::Tick;
+    use std::net::IpAddr;
+
+    #[test]
+    fn limits_per_ip() {
+        let mut config = RateLimitConfig::new();
+        config.handshake_burst = 2.0;
+        let mut limiter = RateLimiter::new(config, 20.0);
+        let now = Tick { value: 0.0 };
+        let a: IpAddr = "10.0.0.1".parse().unwrap();
+        let b: IpAddr = "10.0.0.2".parse().unwrap();
+
+        assert!(limiter.allow_handshake(a, 22, now));
+        assert!(limiter.allow_handshake(a, 22, now));
+        assert!(!limiter.allow_handshake(a, 22, now));
+        assert!(limiter.allow_handshake(b, 22, now));
+        assert!(limiter.allow_unknown(a, 22, now));
+
+        let stats = limiter.stats();
+        assert_eq!(stats.handshake_packets_dropped, 1);
+        assert_eq!(stats.handshake_bytes_dropped, 22);
+        assert_eq!(stats.unknown_packets_dropped, 0);
+    }
+
+    #[test]
+    fn prune_forgets_idle_addresses() {
+        let mut limiter = RateLimiter::new(RateLimitConfig::new(), 20.0);
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        assert!(limiter.allow_handshake(ip, 22, Tick { value: 0.0 }));
+        limiter.prune(Tick { value: 0.0 });
+        assert_eq!(limiter.buckets.len(), 1);
+        limiter.prune(Tick { value: 20.0 });
+        assert!(limiter.buckets.is_empty());
+    }
+
+    #[test]
+    fn address_cap() {
+        let mut config = RateLimitConfig::new();
+        config.max_addresses = 2;
+        let mut limiter = RateLimiter::new(config, 20.0);
+        let now = Tick { value: 0.0 };
+        let a: IpAddr = "10.0.0.1".parse().unwrap();
+        let b: IpAddr = "10.0.0.2".parse().unwrap();
+        let c: IpAddr = "10.0.0.3".parse().unwrap();
+
+        assert!(limiter.allow_handshake(a, 22, now));
+        assert!(limiter.allow_unknown(b, 22, now));
+        assert!(!limiter.allow_handshake(c, 22, now));
+        assert!(limiter.allow_handshake(a, 22, now));
+        assert_eq!(limiter.buckets.len(), 2);
+        assert_eq!(limiter.stats().handshake_packets_dropped, 1);
+
+        // Room again once the idle ones are pruned
+        limiter.prune(Tick { value: 20.0 });
+        assert!(limiter.allow_handshake(c, 22, Tick { value: 20.0 }));
+    }
+}
@@ -0,0 +1,30 @@
+/// Keeps some connection slots free for priority clients (e.g. admins), the ones whose
+/// `ConnectionRequest` carries a priority of at least `min_priority`. Priority is picked by the
+/// client, so it's ignored unless `ServerConfig::accept_hook` is set to vet it. That goes for
+/// `evict` too.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ReservedSlotsConfig {
+    pub slots: usize,     // Out of max_connections, regular clients can't take these
+    pub min_priority: u8, // Clients at or above this are priority clients
+    pub evict: bool,      // When full, kick the lowest priority client for a higher priority one
+}
+
+impl ReservedSlotsConfig {
+    pub fn new() -> Self {
+        Self {
+            slots: 1,
+            min_priority: 1,
+            evict: false,
+        }
+    }
+
+    pub fn is_priority(&self, priority: u8) -> bool {
+        priority >= self.min_priority
+    }
+}
+
+impl Default for ReservedSlotsConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
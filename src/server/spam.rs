@@ -0,0 +1,106 @@
+use crate::packet::Packet;
+use crate::tick::Tick;
+use crate::token_bucket::TokenBucket;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SpamAction {
+    Drop,     // Throw away whatever is over the limit
+    Warn,     // Let it through, but emit `ServerEvent::Spamming`
+    Throttle, // Queue whatever is over the limit and handle it once the connection is back under
+    Kick,     // Disconnect with `DisconnectReason::Spam`
+}
+
+/// Per connection limits on what the server will handle. `byte_burst` has to be at least the
+/// biggest packet a client can send, or that packet never gets through.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpamPolicy {
+    pub packets_per_second: f32,
+    pub packet_burst: f32,
+    pub bytes_per_second: f32,
+    pub byte_burst: f32,
+    pub action: SpamAction,
+    pub grace_period: Duration, // Not enforced for this long after the connection shows up
+}
+
+impl SpamPolicy {
+    pub fn new() -> Self {
+        Self {
+            packets_per_second: 60.0,
+            packet_burst: 75.0,
+            bytes_per_second: 64.0 * 1024.0,
+            byte_burst: 64.0 * 1024.0,
+            action: SpamAction::Kick,
+            grace_period: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Default for SpamPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SpamFilter {
+    packets: TokenBucket,
+    bytes: TokenBucket,
+    pub throttled: VecDeque<(Packet, usize)>, // Packet and size, waiting for the connection to get back under the limit
+    pub last_warning: Option<Tick>,
+}
+
+impl SpamFilter {
+    pub fn new(policy: SpamPolicy, tps: f32, now: Tick) -> Self {
+        Self {
+            packets: TokenBucket::new(policy.packets_per_second, policy.packet_burst, tps, now),
+            bytes: TokenBucket::new(policy.bytes_per_second, policy.byte_burst, tps, now),
+            throttled: VecDeque::new(),
+            last_warning: None,
+        }
+    }
+
+    /// Takes a packet of `size` bytes out of both buckets, returns false if either is short.
+    pub fn allow(&mut self, size: usize, now: Tick) -> bool {
+        let size = size as f32;
+        if self.packets.tokens(now) < 1.0 || self.bytes.tokens(now) < size {
+            return false;
+        }
+
+        self.packets.try_take(1.0, now) && self.bytes.try_take(size, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::spam::{SpamFilter, SpamPolicy};
+    use crate::tick::Tick;
+
+    #[test]
+    fn packet_rate() {
+        let mut policy = SpamPolicy::new();
+        policy.packets_per_second = 20.0;
+        policy.packet_burst = 2.0;
+        let mut filter = SpamFilter::new(policy, 20.0, Tick { value: 0.0 });
+
+        assert!(filter.allow(100, Tick { value: 0.0 }));
+        assert!(filter.allow(100, Tick { value: 0.0 }));
+        assert!(!filter.allow(100, Tick { value: 0.0 }));
+        assert!(filter.allow(100, Tick { value: 1.0 }));
+    }
+
+    #[test]
+    fn byte_rate() {
+        let mut policy = SpamPolicy::new();
+        policy.bytes_per_second = 2000.0;
+        policy.byte_burst = 1000.0;
+        let mut filter = SpamFilter::new(policy, 20.0, Tick { value: 0.0 });
+
+        assert!(filter.allow(600, Tick { value: 0.0 }));
+        assert!(!filter.allow(600, Tick { value: 0.0 }));
+        // Didn't take a packet token either
+        assert!(filter.allow(400, Tick { value: 0.0 }));
+        assert!(filter.allow(100, Tick { value: 1.0 }));
+    }
+}
@@ -0,0 +1,258 @@
+use crate::server::accept::PendingConnection;
+use crate::server::connection::ConnectionIdentifier;
+use crate::tick::Tick;
+use crate::user_data::UserData;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WaitingQueueConfig {
+    pub max_length: usize,         // Clients past this are told the server is full
+    pub timeout: Duration,         // Longest anyone waits for a slot
+    pub update_interval: Duration, // How often clients are told their position
+}
+
+impl WaitingQueueConfig {
+    pub fn new() -> Self {
+        Self {
+            max_length: 64,
+            timeout: Duration::from_secs(60),
+            update_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Default for WaitingQueueConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A client that answered our challenge while the server was full.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct WaitingClient {
+    pub client_identifier: ConnectionIdentifier, // Id the client picked, and its address
+    pub secret: u64,                             // From the ChallengeResponse
+    pub priority: u8,
+    pub user_data: UserData,
+    pub queued_at: Tick,
+    pub last_heard: Tick,
+}
+
+impl WaitingClient {
+    pub fn pending(&self) -> PendingConnection {
+        PendingConnection {
+            client_identifier: self.client_identifier,
+            priority: self.priority,
+            user_data: self.user_data,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WaitingQueue {
+    pub config: WaitingQueueConfig,
+    clients: VecDeque<WaitingClient>,
+    timeout: Tick,
+    update_interval: Tick,
+}
+
+impl WaitingQueue {
+    pub fn new(config: WaitingQueueConfig, tps: f32) -> Self {
+        Self {
+            config,
+            clients: VecDeque::new(),
+            timeout: Tick::from_duration(config.timeout, tps),
+            update_interval: Tick::from_duration(config.update_interval, tps),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    pub fn clients(&self) -> impl Iterator<Item = &WaitingClient> {
+        self.clients.iter()
+    }
+
+    /// 1 is next in line, None if the client isn't waiting.
+    pub fn position(&self, client_identifier: ConnectionIdentifier) -> Option<usize> {
+        self.clients
+            .iter()
+            .position(|client| client.client_identifier == client_identifier)
+            .map(|index| index + 1)
+    }
+
+    /// Adds the client to the back of the queue, returns its position or None if the queue is
+    /// full. Clients that are already waiting keep their place.
+    pub fn push(&mut self, pending: PendingConnection, secret: u64, now: Tick) -> Option<usize> {
+        if let Some(position) = self.heard_from(pending.client_identifier, now) {
+            return Some(position);
+        }
+
+        if self.clients.len() >= self.config.max_length {
+            return None;
+        }
+
+        self.clients.push_back(WaitingClient {
+            client_identifier: pending.client_identifier,
+            secret,
+            priority: pending.priority,
+            user_data: pending.user_data,
+            queued_at: now,
+            last_heard: now,
+        });
+        Some(self.clients.len())
+    }
+
+    /// Returns the client's position if it's waiting.
+    pub fn heard_from(
+        &mut self,
+        client_identifier: ConnectionIdentifier,
+        now: Tick,
+    ) -> Option<usize> {
+        let position = self.position(client_identifier)?;
+        self.clients[position - 1].last_heard = now;
+        Some(position)
+    }
+
+    pub fn pop(&mut self) -> Option<WaitingClient> {
+        self.clients.pop_front()
+    }
+
+    /// Removes the first client in line that `admit` lets in, the ones before it stay waiting.
+    pub fn pop_first(
+        &mut self,
+        admit: impl FnMut(&WaitingClient) -> bool,
+    ) -> Option<WaitingClient> {
+        let index = self.clients.iter().position(admit)?;
+        self.clients.remove(index)
+    }
+
+    pub fn remove(&mut self, client_identifier: ConnectionIdentifier) -> bool {
+        let Some(position) = self.position(client_identifier) else {
+            return false;
+        };
+        self.clients.remove(position - 1);
+        true
+    }
+
+    /// Removes every client `matches` picks out, in line order.
+    pub fn remove_matching(
+        &mut self,
+        mut matches: impl FnMut(&WaitingClient) -> bool,
+    ) -> Vec<WaitingClient> {
+        let mut removed = vec![];
+        self.clients.retain(|client| {
+            let keep = !matches(client);
+            if !keep {
+                removed.push(*client);
+            }
+            keep
+        });
+        removed
+    }
+
+    /// Removes clients that waited too long or went quiet for `idle_timeout`.
+    pub fn remove_timed_out(&mut self, now: Tick, idle_timeout: Tick) -> Vec<WaitingClient> {
+        let mut timed_out = vec![];
+        self.clients.retain(|client| {
+            let waited = now.value - client.queued_at.value;
+            let idle = now.value - client.last_heard.value;
+            let keep = waited < self.timeout.value && idle < idle_timeout.value;
+            if !keep {
+                timed_out.push(*client);
+            }
+            keep
+        });
+        timed_out
+    }
+
+    pub fn is_update_due(&self, now: Tick) -> bool {
+        self.update_interval.value <= 1.0 || now.value % self.update_interval.value.round() == 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::packet::UnetId;
+    use crate::server::accept::PendingConnection;
+    use crate::server::connection::ConnectionIdentifier;
+    use crate::server::waiting_queue::{WaitingQueue, WaitingQueueConfig};
+    use crate::tick::Tick;
+    use crate::user_data::UserData;
+    use std::time::Duration;
+
+    fn client(i: u64) -> ConnectionIdentifier {
+        ConnectionIdentifier::new(UnetId(i), "10.0.0.1:20000".parse().unwrap())
+    }
+
+    fn pending(i: u64, priority: u8) -> PendingConnection {
+        PendingConnection {
+            client_identifier: client(i),
+            priority,
+            user_data: UserData::default(),
+        }
+    }
+
+    #[test]
+    fn first_in_first_out() {
+        let mut queue = WaitingQueue::new(WaitingQueueConfig::new(), 20.0);
+        let now = Tick { value: 0.0 };
+        assert_eq!(queue.push(pending(1, 0), 0, now), Some(1));
+        assert_eq!(queue.push(pending(2, 0), 0, now), Some(2));
+        assert_eq!(queue.push(pending(1, 0), 0, now), Some(1)); // Already waiting
+        assert_eq!(queue.pop().unwrap().client_identifier, client(1));
+        assert_eq!(queue.position(client(2)), Some(1));
+    }
+
+    #[test]
+    fn pop_first() {
+        let mut queue = WaitingQueue::new(WaitingQueueConfig::new(), 20.0);
+        let now = Tick { value: 0.0 };
+        queue.push(pending(1, 0), 0, now);
+        queue.push(pending(2, 1), 0, now);
+        queue.push(pending(3, 1), 0, now);
+        let next = queue.pop_first(|client| client.priority > 0).unwrap();
+        assert_eq!(next.client_identifier, client(2));
+        assert_eq!(queue.position(client(1)), Some(1));
+        assert_eq!(queue.position(client(3)), Some(2));
+        assert!(queue.pop_first(|client| client.priority > 1).is_none());
+    }
+
+    #[test]
+    fn max_length() {
+        let mut config = WaitingQueueConfig::new();
+        config.max_length = 1;
+        let mut queue = WaitingQueue::new(config, 20.0);
+        let now = Tick { value: 0.0 };
+        assert_eq!(queue.push(pending(1, 0), 0, now), Some(1));
+        assert_eq!(queue.push(pending(2, 0), 0, now), None);
+    }
+
+    #[test]
+    fn timeouts() {
+        let mut config = WaitingQueueConfig::new();
+        config.timeout = Duration::from_secs(1);
+        let mut queue = WaitingQueue::new(config, 20.0);
+        let idle_timeout = Tick { value: 10.0 };
+        queue.push(pending(1, 0), 0, Tick { value: 0.0 });
+        queue.push(pending(2, 0), 0, Tick { value: 5.0 });
+
+        // Client 1 keeps talking, client 2 doesn't
+        queue.heard_from(client(1), Tick { value: 12.0 });
+        let timed_out = queue.remove_timed_out(Tick { value: 15.0 }, idle_timeout);
+        assert_eq!(timed_out.len(), 1);
+        assert_eq!(timed_out[0].client_identifier, client(2));
+
+        // But nobody waits longer than the queue timeout
+        queue.heard_from(client(1), Tick { value: 20.0 });
+        let timed_out = queue.remove_timed_out(Tick { value: 20.0 }, idle_timeout);
+        assert_eq!(timed_out.len(), 1);
+        assert!(queue.is_empty());
+    }
+}
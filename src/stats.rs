@@ -0,0 +1,230 @@
+use crate::replay_window::ReplayCheck;
+use crate::rolling_average::RollingAverage;
+use crate::tick::Tick;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const PING_TIMEOUT: Duration = Duration::from_secs(1); // Pings not answered by then count as lost
+const PING_LOSS_SAMPLES: usize = 32;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct NetworkStats {
+    pub rtt: Duration, // Smoothed round trip time
+    pub rtt_variance: Duration,
+    pub packet_loss: f32, // Percentage of recent pings that were never answered
+    pub bytes_sent_per_second: f32,
+    pub bytes_received_per_second: f32,
+    pub packets_sent: u64,
+    pub packets_received: u64,
+    pub packets_dropped: u64, // Received, but thrown away (out of order, duplicates, ...)
+    pub packets_late: u64,    // Arrived after a newer packet, but still accepted
+    pub packets_duplicated: u64, // Already received, dropped
+}
+
+#[derive(Clone, Debug)]
+pub struct StatsTracker {
+    stats: NetworkStats,
+    has_rtt_sample: bool,
+    pings_in_flight: VecDeque<(u64, Instant)>,
+    next_ping_id: u64,
+    ping_frequency: Tick,
+    ticks_since_last_ping: Tick,
+    rolling_ping_loss: RollingAverage,
+    bytes_sent_this_tick: f32,
+    bytes_received_this_tick: f32,
+    rolling_bytes_sent: RollingAverage,
+    rolling_bytes_received: RollingAverage,
+    tps: f32,
+}
+
+impl StatsTracker {
+    pub fn new(ping_frequency: Tick, tps: f32) -> Self {
+        let samples = (tps.ceil() as usize).max(1); // About one second worth of ticks
+        Self {
+            stats: NetworkStats::default(),
+            has_rtt_sample: false,
+            pings_in_flight: VecDeque::new(),
+            next_ping_id: 0,
+            ping_frequency,
+            ticks_since_last_ping: Tick { value: 0.0 },
+            rolling_ping_loss: RollingAverage::new(PING_LOSS_SAMPLES),
+            bytes_sent_this_tick: 0.0,
+            bytes_received_this_tick: 0.0,
+            rolling_bytes_sent: RollingAverage::new(samples),
+            rolling_bytes_received: RollingAverage::new(samples),
+            tps,
+        }
+    }
+
+    pub fn stats(&self) -> NetworkStats {
+        self.stats
+    }
+
+    pub fn packet_sent(&mut self, bytes: usize) {
+        self.stats.packets_sent += 1;
+        self.bytes_sent_this_tick += bytes as f32;
+    }
+
+    pub fn packet_received(&mut self, bytes: usize) {
+        self.stats.packets_received += 1;
+        self.bytes_received_this_tick += bytes as f32;
+    }
+
+    pub fn packet_dropped(&mut self) {
+        self.stats.packets_dropped += 1;
+    }
+
+    /// Counts the outcome of a replay window check, returns whether the packet was accepted.
+    pub fn replay_checked(&mut self, check: ReplayCheck) -> bool {
+        match check {
+            ReplayCheck::New => {}
+            ReplayCheck::Late => self.stats.packets_late += 1,
+            ReplayCheck::Duplicate => {
+                self.stats.packets_duplicated += 1;
+                self.packet_dropped();
+            }
+            ReplayCheck::TooOld => self.packet_dropped(),
+        }
+
+        check.is_accepted()
+    }
+
+    /// Returns the id of the next ping to send, if it's time to send one.
+    pub fn next_ping(&mut self, now: Instant) -> Option<u64> {
+        if self.ticks_since_last_ping < self.ping_frequency {
+            return None;
+        }
+
+        let ping_id = self.next_ping_id;
+        self.next_ping_id += 1;
+        self.ticks_since_last_ping.value = 0.0;
+        self.pings_in_flight.push_back((ping_id, now));
+
+        Some(ping_id)
+    }
+
+    pub fn pong_received(&mut self, ping_id: u64, now: Instant) {
+        let Some(position) = self
+            .pings_in_flight
+            .iter()
+            .position(|(id, _)| *id == ping_id)
+        else {
+            // Late or duplicate, it was already counted as lost
+            return;
+        };
+
+        let (_, sent_at) = self.pings_in_flight.remove(position).unwrap();
+        self.rolling_ping_loss.add(0.0);
+        self.add_rtt_sample(now - sent_at);
+    }
+
+    /// Same smoothing as TCP (RFC 6298).
+    fn add_rtt_sample(&mut self, sample: Duration) {
+        if !self.has_rtt_sample {
+            self.has_rtt_sample = true;
+            self.stats.rtt = sample;
+            self.stats.rtt_variance = sample / 2;
+            return;
+        }
+
+        let difference = self.stats.rtt.abs_diff(sample);
+        self.stats.rtt_variance = (self.stats.rtt_variance * 3 + difference) / 4;
+        self.stats.rtt = (self.stats.rtt * 7 + sample) / 8;
+    }
+
+    pub fn tick(&mut self, now: Instant) {
+        while let Some((_, sent_at)) = self.pings_in_flight.front() {
+            if now - *sent_at < PING_TIMEOUT {
+                break;
+            }
+            self.pings_in_flight.pop_front();
+            self.rolling_ping_loss.add(1.0);
+        }
+        self.stats.packet_loss = self.rolling_ping_loss.value() * 100.0;
+
+        self.rolling_bytes_sent.add(self.bytes_sent_this_tick);
+        self.rolling_bytes_received
+            .add(self.bytes_received_this_tick);
+        self.bytes_sent_this_tick = 0.0;
+        self.bytes_received_this_tick = 0.0;
+        self.stats.bytes_sent_per_second = self.rolling_bytes_sent.value() * self.tps;
+        self.stats.bytes_received_per_second = self.rolling_bytes_received.value() * self.tps;
+
+        self.ticks_since_last_ping.value += 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::StatsTracker;
+    use crate::tick::Tick;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn first_rtt_sample() {
+        let mut tracker = StatsTracker::new(Tick { value: 0.0 }, 20.0);
+        let now = Instant::now();
+        let ping_id = tracker.next_ping(now).unwrap();
+        tracker.pong_received(ping_id, now + Duration::from_millis(100));
+
+        let stats = tracker.stats();
+        assert_eq!(stats.rtt, Duration::from_millis(100));
+        assert_eq!(stats.rtt_variance, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn smoothed_rtt() {
+        let mut tracker = StatsTracker::new(Tick { value: 0.0 }, 20.0);
+        let now = Instant::now();
+        let first = tracker.next_ping(now).unwrap();
+        let second = tracker.next_ping(now).unwrap();
+        tracker.pong_received(first, now + Duration::from_millis(100));
+        tracker.pong_received(second, now + Duration::from_millis(180));
+
+        let stats = tracker.stats();
+        assert_eq!(stats.rtt, Duration::from_millis(110));
+        assert_eq!(
+            stats.rtt_variance,
+            Duration::from_millis(57) + Duration::from_micros(500)
+        );
+    }
+
+    #[test]
+    fn ping_frequency() {
+        let mut tracker = StatsTracker::new(Tick { value: 2.0 }, 20.0);
+        let now = Instant::now();
+        assert!(tracker.next_ping(now).is_none());
+        tracker.tick(now);
+        tracker.tick(now);
+        assert!(tracker.next_ping(now).is_some());
+        assert!(tracker.next_ping(now).is_none());
+    }
+
+    #[test]
+    fn unanswered_pings_are_lost() {
+        let mut tracker = StatsTracker::new(Tick { value: 0.0 }, 20.0);
+        let now = Instant::now();
+        for _ in 0..8 {
+            tracker.next_ping(now).unwrap();
+        }
+        tracker.tick(now + Duration::from_secs(2));
+        assert_eq!(tracker.stats().packet_loss, 25.0);
+    }
+
+    #[test]
+    fn bytes_per_second() {
+        let mut tracker = StatsTracker::new(Tick { value: 0.0 }, 2.0);
+        let now = Instant::now();
+        tracker.packet_sent(100);
+        tracker.packet_received(10);
+        tracker.tick(now);
+        tracker.packet_sent(100);
+        tracker.tick(now);
+
+        let stats = tracker.stats();
+        assert_eq!(stats.bytes_sent_per_second, 200.0);
+        assert_eq!(stats.bytes_received_per_second, 10.0);
+        assert_eq!(stats.packets_sent, 2);
+        assert_eq!(stats.packets_received, 1);
+    }
+}
@@ -1,5 +1,6 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
+#[allow(dead_code)]
 pub struct ConnectToken {
     create_timestamp: u64,
     expire_timestamp: u64,
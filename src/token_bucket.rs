@@ -0,0 +1,81 @@
+use crate::tick::Tick;
+
+/// Allows `rate` per second on average, with bursts of up to `burst`. Refills lazily from the
+/// tick it's asked at, so idle buckets don't need to be ticked.
+#[derive(Clone, Debug)]
+pub struct TokenBucket {
+    rate_per_tick: f32,
+    burst: f32,
+    tokens: f32,
+    last_refill: Tick,
+}
+
+impl TokenBucket {
+    /// Starts out full.
+    pub fn new(rate: f32, burst: f32, tps: f32, now: Tick) -> Self {
+        Self {
+            rate_per_tick: rate / tps,
+            burst,
+            tokens: burst,
+            last_refill: now,
+        }
+    }
+
+    pub fn tokens(&mut self, now: Tick) -> f32 {
+        self.refill(now);
+        self.tokens
+    }
+
+    pub fn is_full(&mut self, now: Tick) -> bool {
+        self.tokens(now) >= self.burst
+    }
+
+    /// Takes `amount` tokens, returns false (and takes nothing) if there aren't enough.
+    pub fn try_take(&mut self, amount: f32, now: Tick) -> bool {
+        self.refill(now);
+        if self.tokens < amount {
+            return false;
+        }
+
+        self.tokens -= amount;
+        true
+    }
+
+    fn refill(&mut self, now: Tick) {
+        let elapsed = (now.value - self.last_refill.value).max(0.0);
+        self.tokens = (self.tokens + elapsed * self.rate_per_tick).min(self.burst);
+        self.last_refill = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tick::Tick;
+    use crate::token_bucket::TokenBucket;
+
+    #[test]
+    fn burst() {
+        let now = Tick { value: 0.0 };
+        let mut bucket = TokenBucket::new(1.0, 3.0, 20.0, now);
+        assert!(bucket.try_take(1.0, now));
+        assert!(bucket.try_take(1.0, now));
+        assert!(bucket.try_take(1.0, now));
+        assert!(!bucket.try_take(1.0, now));
+    }
+
+    #[test]
+    fn refills_at_rate() {
+        let mut bucket = TokenBucket::new(10.0, 10.0, 20.0, Tick { value: 0.0 });
+        assert!(bucket.try_take(10.0, Tick { value: 0.0 }));
+        assert_eq!(bucket.tokens(Tick { value: 10.0 }), 5.0);
+        assert!(bucket.is_full(Tick { value: 100.0 }));
+    }
+
+    #[test]
+    fn failed_take_takes_nothing() {
+        let now = Tick { value: 0.0 };
+        let mut bucket = TokenBucket::new(1.0, 2.0, 20.0, now);
+        assert!(!bucket.try_take(3.0, now));
+        assert_eq!(bucket.tokens(now), 2.0);
+    }
+}
@@ -0,0 +1,83 @@
+/// Application data the client hands the server during the handshake, e.g. an auth ticket,
+/// player name or build id. Fixed capacity so packets stay `Copy`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct UserData {
+    bytes: [u8; Self::MAX_SIZE],
+    len: u16,
+}
+
+impl UserData {
+    pub const MAX_SIZE: usize = 256;
+
+    /// None if `bytes` is longer than `MAX_SIZE`.
+    pub fn new(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() > Self::MAX_SIZE {
+            return None;
+        }
+
+        let mut user_data = Self::default();
+        user_data.bytes[..bytes.len()].copy_from_slice(bytes);
+        user_data.len = bytes.len() as u16;
+        Some(user_data)
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Length prefixed, anything past the claimed length is ignored. None if the length is
+    /// missing, over `MAX_SIZE` or more than `bytes` holds.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let len = u16::from_be_bytes(bytes.get(..2)?.try_into().unwrap()) as usize;
+        Self::new(bytes.get(2..2 + len)?)
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut output = vec![];
+        output.extend_from_slice(&self.len.to_be_bytes());
+        output.extend_from_slice(self.as_slice());
+        output
+    }
+}
+
+impl Default for UserData {
+    fn default() -> Self {
+        Self {
+            bytes: [0; Self::MAX_SIZE],
+            len: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::user_data::UserData;
+
+    #[test]
+    fn round_trip() {
+        let user_data = UserData::new(b"player one").unwrap();
+        assert_eq!(user_data.as_slice(), b"player one");
+        assert_eq!(UserData::from_bytes(&user_data.as_bytes()), Some(user_data));
+        assert!(UserData::from_bytes(&UserData::default().as_bytes())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn bounded() {
+        assert!(UserData::new(&[0; UserData::MAX_SIZE]).is_some());
+        assert!(UserData::new(&[0; UserData::MAX_SIZE + 1]).is_none());
+
+        // Claims more than it has, or more than we'd take
+        assert_eq!(UserData::from_bytes(&[0, 10, 1, 2, 3]), None);
+        let mut bytes = vec![1, 1];
+        bytes.resize(2 + 257, 0);
+        assert_eq!(UserData::from_bytes(&bytes), None);
+        assert_eq!(UserData::from_bytes(&[0]), None);
+        assert_eq!(UserData::from_bytes(&[]), None);
+    }
+}
@@ -0,0 +1,62 @@
+use std::sync::{Arc, Mutex};
+use unet::client::ClientState;
+use unet::config::test::{connect_clients, multi_client_test_config, tick_all};
+use unet::packet::disconnect::DisconnectReason;
+use unet::server::accept::PendingConnection;
+use unet::server::UnetServer;
+use unet::user_data::UserData;
+
+#[test]
+fn hook_decides_who_gets_in() {
+    let (mut server_config, mut client_configs) = multi_client_test_config(3);
+    let seen = Arc::new(Mutex::new(vec![]));
+    let hook_seen = seen.clone();
+    server_config.accept_hook = Some(Box::new(move |pending: &PendingConnection| {
+        hook_seen.lock().unwrap().push(*pending);
+        match pending.user_data.as_slice() {
+            b"build 2" => Ok(()),
+            b"cheater" => Err(DisconnectReason::Banned),
+            _ => Err(DisconnectReason::Rejected),
+        }
+    }));
+    let mut server = UnetServer::from_config(server_config).unwrap();
+
+    let user_data = [&b"build 2"[..], b"build 1", b"cheater"];
+    for (client_config, user_data) in client_configs.iter_mut().zip(user_data) {
+        client_config.user_data = UserData::new(user_data).unwrap();
+    }
+    let mut clients = connect_clients(client_configs);
+
+    tick_all(&mut server, &mut clients, 5);
+    assert_eq!(clients[0].state, ClientState::Connected);
+    assert_eq!(
+        clients[1].state,
+        ClientState::Disconnected(DisconnectReason::Rejected)
+    );
+    assert_eq!(
+        clients[2].state,
+        ClientState::Disconnected(DisconnectReason::Banned)
+    );
+    assert_eq!(server.connection_count(), 1);
+
+    // Asked once per client, with what it sent
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 3);
+    assert_eq!(seen[0].client_identifier.id, clients[0].id);
+    assert_eq!(seen[0].user_data.as_slice(), b"build 2");
+
+    let connection = server.connections.iter().flatten().next().unwrap();
+    assert_eq!(connection.user_data.as_slice(), b"build 2");
+}
+
+#[test]
+fn everyone_gets_in_without_a_hook() {
+    let (server_config, client_configs) = multi_client_test_config(2);
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut clients = connect_clients(client_configs);
+
+    tick_all(&mut server, &mut clients, 5);
+    assert!(clients
+        .iter()
+        .all(|client| client.state == ClientState::Connected));
+}
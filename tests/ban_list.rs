@@ -0,0 +1,132 @@
+use std::time::Duration;
+use unet::client::{ClientState, UnetClient};
+use unet::clock::ManualClock;
+use unet::config::test::{
+    conditioned_test_config, connect_clients, multi_client_test_config, test_config, tick_all,
+};
+use unet::network::link_conditioner::LinkConditionerConfig;
+use unet::packet::disconnect::DisconnectReason;
+use unet::packet::UnetId;
+use unet::server::ban::{BanList, BanTarget};
+use unet::server::waiting_queue::WaitingQueueConfig;
+use unet::server::UnetServer;
+
+#[test]
+fn banned_client_cannot_connect() {
+    let (mut server_config, mut client_config) = test_config();
+    client_config.id = Some(UnetId(0xbad));
+    server_config
+        .ban_list
+        .ban(BanTarget::Id(UnetId(0xbad)), None);
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+    tick_all(&mut server, &mut [&mut client], 4);
+
+    assert_eq!(
+        client.state,
+        ClientState::Disconnected(DisconnectReason::Banned)
+    );
+    assert_eq!(server.connection_count(), 0);
+}
+
+#[test]
+fn banning_kicks_connected_clients() {
+    let (server_config, client_config) = test_config();
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+    tick_all(&mut server, &mut [&mut client], 4);
+    assert_eq!(client.state, ClientState::Connected);
+
+    server.ban("10.0.0.0/8".parse().unwrap(), None);
+    assert_eq!(server.connection_count(), 0);
+    client.tick();
+    assert_eq!(
+        client.state,
+        ClientState::Disconnected(DisconnectReason::Banned)
+    );
+}
+
+#[test]
+fn bans_expire() {
+    let clock = ManualClock::new();
+    let link = LinkConditionerConfig::new();
+    let (server_config, client_config) = conditioned_test_config(clock.clone(), link, link, 0);
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    server.ban("10.0.0.0/8".parse().unwrap(), Some(Duration::from_secs(1)));
+
+    let mut client = UnetClient::from_config(client_config).unwrap();
+    tick_all(&mut server, &mut [&mut client], 4);
+    assert_eq!(
+        client.state,
+        ClientState::Disconnected(DisconnectReason::Banned)
+    );
+
+    clock.advance(Duration::from_secs(2));
+    for _ in 0..20 {
+        server.tick();
+    }
+    assert_eq!(server.ban_list().bans().count(), 0);
+}
+
+#[test]
+fn allow_list_only() {
+    let (mut server_config, client_config) = test_config();
+    server_config.ban_list.allow_list_only = true;
+    server_config.ban_list.allow("127.0.0.1".parse().unwrap());
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+    tick_all(&mut server, &mut [&mut client], 4);
+    assert_eq!(
+        client.state,
+        ClientState::Disconnected(DisconnectReason::Banned)
+    );
+
+    let (mut server_config, client_config) = test_config();
+    server_config.ban_list.allow_list_only = true;
+    server_config.ban_list.allow("10.0.0.0/24".parse().unwrap());
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+    tick_all(&mut server, &mut [&mut client], 4);
+    assert_eq!(client.state, ClientState::Connected);
+}
+
+#[test]
+fn banning_removes_waiting_clients() {
+    let (mut server_config, client_configs) = multi_client_test_config(2);
+    server_config.max_connections = 1;
+    server_config.waiting_queue = Some(WaitingQueueConfig::new());
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut clients = connect_clients(client_configs);
+
+    tick_all(&mut server, &mut clients, 5);
+    assert_eq!(clients[0].state, ClientState::Connected);
+    assert_eq!(clients[1].state, ClientState::Queued);
+
+    // Both of them, so the slot that frees up isn't handed to the banned client in line
+    server.ban("10.0.0.0/24".parse().unwrap(), None);
+    tick_all(&mut server, &mut clients, 5);
+    assert_eq!(server.connection_count(), 0);
+    assert_eq!(server.waiting_count(), 0);
+    for client in &clients {
+        assert_eq!(
+            client.state,
+            ClientState::Disconnected(DisconnectReason::Banned)
+        );
+    }
+}
+
+#[test]
+fn load_and_save() {
+    let path = std::env::temp_dir().join(format!("unet-ban-list-{}.txt", std::process::id()));
+    let mut ban_list = BanList::new();
+    ban_list.ban("id:bad".parse().unwrap(), None);
+    ban_list.allow("::1".parse().unwrap());
+    ban_list.save(&path).unwrap();
+
+    let loaded = BanList::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, ban_list);
+}
@@ -1,4 +1,4 @@
-use unet::client::UnetClient;
+use unet::client::{ClientState, UnetClient};
 use unet::config::test::test_config;
 use unet::packet::data::Data;
 use unet::packet::Packet;
@@ -27,3 +27,35 @@ fn client_sends_packets_out_of_order() {
     client.send_keep_alive_packet().unwrap();
     server.tick();
 }
+
+#[test]
+fn server_accepts_late_packets_once() {
+    let (server_config, client_config) = test_config();
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    for _ in 0..5 {
+        assert!(client.tick());
+        server.tick();
+    }
+    assert_eq!(client.state, ClientState::Connected);
+
+    let connection_identifier = server.connections[0]
+        .as_ref()
+        .unwrap()
+        .connection_identifier;
+    let dropped = server.stats(connection_identifier).unwrap().packets_dropped;
+
+    for sequence in [50, 40, 40, 50, 45] {
+        client.sequence = sequence;
+        client
+            .send_packet(Packet::Data(Data::new(client.id, 10)))
+            .unwrap();
+    }
+    server.tick();
+
+    let stats = server.stats(connection_identifier).unwrap();
+    assert_eq!(stats.packets_late, 2);
+    assert_eq!(stats.packets_duplicated, 2);
+    assert_eq!(stats.packets_dropped, dropped + 2);
+}
@@ -26,11 +26,19 @@ fn client_timeout() {
     server.tick();
     println!();
 
+    println!("client::tick 2");
+    client.tick();
+    println!();
+
     println!("server::tick 2");
     server.tick();
     println!();
 
-    println!("client::tick 2");
+    println!("server::tick 3");
+    server.tick();
+    println!();
+
+    println!("client::tick 3");
     client.tick();
     println!();
 
@@ -0,0 +1,58 @@
+use std::time::Duration;
+use unet::client::{ClientState, UnetClient};
+use unet::clock::ManualClock;
+use unet::config::test::{conditioned_test_config, run};
+use unet::congestion::{CongestionConfig, CongestionMode};
+use unet::network::link_conditioner::LinkConditionerConfig;
+use unet::packet::data::Data;
+use unet::packet::Packet;
+use unet::server::UnetServer;
+
+#[test]
+fn congestion_control_throttles_send_queue() {
+    let clock = ManualClock::new();
+    let mut link = LinkConditionerConfig::new();
+    link.latency = Duration::from_millis(200);
+    let (mut server_config, mut client_config) =
+        conditioned_test_config(clock.clone(), link, link, 0);
+    server_config.spam_policy = None;
+    client_config.congestion_control = Some(CongestionConfig::new());
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    run(&clock, &mut server, &mut client, Duration::from_secs(5));
+    assert_eq!(client.state, ClientState::Connected);
+
+    // 400ms RTT is over the threshold
+    assert_eq!(client.congestion_mode(), Some(CongestionMode::Bad));
+    assert_eq!(client.allowed_send_rate(), Some(10.0));
+
+    for i in 0..100 {
+        client.send(Packet::Data(Data::new(client.id, i)));
+    }
+    run(&clock, &mut server, &mut client, Duration::from_secs(2));
+
+    let sent = 100 - client.send_queue.len();
+    assert!((20..=30).contains(&sent), "{sent}");
+}
+
+#[test]
+fn no_congestion_control_by_default() {
+    let clock = ManualClock::new();
+    let link = LinkConditionerConfig::new();
+    let (mut server_config, client_config) = conditioned_test_config(clock.clone(), link, link, 0);
+    server_config.spam_policy = None;
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    run(&clock, &mut server, &mut client, Duration::from_secs(1));
+    assert_eq!(client.allowed_send_rate(), None);
+
+    for i in 0..100 {
+        client.send(Packet::Data(Data::new(client.id, i)));
+    }
+    run(&clock, &mut server, &mut client, Duration::from_millis(100));
+    assert!(client.send_queue.is_empty());
+}
@@ -0,0 +1,120 @@
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use unet::client::{ClientState, UnetClient};
+use unet::config::client::ClientConfig;
+use unet::config::test::{test_client_addr, test_server_config, tick_all};
+use unet::network::{Transport, VirtualHub, VirtualNetwork};
+use unet::packet::keep_alive::KeepAlive;
+use unet::packet::path_response::PathResponse;
+use unet::packet::Packet;
+use unet::server::connection::ConnectionIdentifier;
+use unet::server::event::ServerEvent;
+use unet::server::UnetServer;
+
+/// Lets the test swap the client's address, like a NAT rebinding its port.
+#[derive(Clone, Debug)]
+struct RebindingTransport {
+    inner: Arc<Mutex<VirtualNetwork>>,
+}
+
+impl Transport for RebindingTransport {
+    fn send_to(&self, buf: &[u8], to: SocketAddr) -> io::Result<usize> {
+        self.inner.lock().unwrap().send_to(buf, to)
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.inner.lock().unwrap().recv_from(buf)
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.lock().unwrap().local_addr()
+    }
+}
+
+fn setup() -> (VirtualHub, UnetServer, UnetClient, RebindingTransport) {
+    let hub = VirtualHub::new();
+
+    let mut server_config = test_server_config(&hub);
+    server_config.connection_migration = true;
+
+    let transport = RebindingTransport {
+        inner: Arc::new(Mutex::new(hub.endpoint(test_client_addr(0)).unwrap())),
+    };
+    let mut client_config = ClientConfig::new();
+    client_config.target = server_config.addr;
+    client_config.transport = Some(Box::new(transport.clone()));
+
+    let server = UnetServer::from_config(server_config).unwrap();
+    let client = UnetClient::from_config(client_config).unwrap();
+
+    (hub, server, client, transport)
+}
+
+#[test]
+fn connection_follows_client_to_new_address() {
+    let (hub, mut server, mut client, transport) = setup();
+    tick_all(&mut server, &mut [&mut client], 3);
+    assert_eq!(client.state, ClientState::Connected);
+
+    let session_id = client.session_id().unwrap();
+    let old = ConnectionIdentifier::new(session_id, test_client_addr(0));
+    let new = ConnectionIdentifier::new(session_id, test_client_addr(1));
+    assert!(server.stats(old).is_some());
+
+    *transport.inner.lock().unwrap() = hub.endpoint(new.addr).unwrap();
+    tick_all(&mut server, &mut [&mut client], 20);
+
+    assert_eq!(client.state, ClientState::Connected);
+    assert_eq!(server.connection_count(), 1);
+    assert!(server.stats(old).is_none());
+    assert!(server.stats(new).is_some());
+    assert_eq!(
+        server.poll_event(),
+        Some(ServerEvent::ConnectionMigrated { from: old, to: new })
+    );
+    assert_eq!(server.poll_event(), None);
+
+    // Well past the timeout, the connection is still alive on its new address
+    tick_all(&mut server, &mut [&mut client], 200);
+    assert_eq!(client.state, ClientState::Connected);
+    assert!(server.stats(new).is_some());
+}
+
+#[test]
+fn wrong_secret_does_not_migrate() {
+    let (hub, mut server, mut client, _transport) = setup();
+    tick_all(&mut server, &mut [&mut client], 3);
+    assert_eq!(client.state, ClientState::Connected);
+
+    // Knows the session id, but not the secret
+    let session_id = client.session_id().unwrap();
+    let server_addr = server.local_addr().unwrap();
+    let attacker = hub.endpoint(test_client_addr(1)).unwrap();
+    let keep_alive = Packet::KeepAlive(KeepAlive::new(session_id));
+    attacker
+        .send_to(&keep_alive.as_bytes(), server_addr)
+        .unwrap();
+    server.tick();
+
+    let mut buf = [0; 64];
+    let (n, _) = attacker.recv_from(&mut buf).unwrap();
+    let Some(Packet::PathChallenge(path_challenge)) = Packet::from_bytes(&buf[..n]) else {
+        panic!("Expected a PathChallenge");
+    };
+
+    let path_response = Packet::PathResponse(PathResponse::new(
+        session_id,
+        path_challenge.challenge,
+        1234,
+    ));
+    attacker
+        .send_to(&path_response.as_bytes(), server_addr)
+        .unwrap();
+    tick_all(&mut server, &mut [&mut client], 5);
+
+    assert_eq!(server.poll_event(), None);
+    let old = ConnectionIdentifier::new(session_id, test_client_addr(0));
+    assert!(server.stats(old).is_some());
+    assert_eq!(client.state, ClientState::Connected);
+}
@@ -0,0 +1,51 @@
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use unet::client::{ClientState, UnetClient};
+use unet::config::test::test_config;
+use unet::network::Transport;
+use unet::server::UnetServer;
+
+#[derive(Debug)]
+struct CountingTransport {
+    inner: Box<dyn Transport>,
+    sent: Arc<AtomicUsize>,
+}
+
+impl Transport for CountingTransport {
+    fn send_to(&self, buf: &[u8], to: SocketAddr) -> io::Result<usize> {
+        self.sent.fetch_add(1, Ordering::SeqCst);
+        self.inner.send_to(buf, to)
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        self.inner.recv_from(buf)
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+}
+
+#[test]
+fn custom_transport() {
+    let (server_config, mut client_config) = test_config();
+    let sent = Arc::new(AtomicUsize::new(0));
+    client_config.transport = Some(Box::new(CountingTransport {
+        inner: client_config.transport.take().unwrap(),
+        sent: sent.clone(),
+    }));
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    client.tick(); // ConnectionRequest
+    server.tick();
+    client.tick(); // ChallengeResponse
+    server.tick();
+    client.tick();
+
+    assert_eq!(client.state, ClientState::Connected);
+    assert_eq!(sent.load(Ordering::SeqCst), 2);
+}
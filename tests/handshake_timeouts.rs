@@ -0,0 +1,95 @@
+use unet::client::{Action, ClientState, UnetClient};
+use unet::config::test::{test_client_addr, test_config, test_server_config};
+use unet::network::{Transport, VirtualHub};
+use unet::packet::challenge_response::ChallengeResponse;
+use unet::packet::connection_request::ConnectionRequest;
+use unet::packet::disconnect::DisconnectReason;
+use unet::packet::{Packet, PacketKind, UnetId};
+use unet::server::UnetServer;
+use unet::tick::Tick;
+
+#[test]
+fn handshake_packets_are_paced() {
+    let (_server_config, mut client_config) = test_config();
+    client_config.action_trace = true;
+    client_config.handshake_resend_interval = Tick { value: 4.0 };
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    let mut sent = 0;
+    for _ in 0..20 {
+        client.tick();
+        sent += client
+            .action_trace
+            .iter()
+            .filter(|action| **action == Action::SendPacket(PacketKind::ConnectionRequest))
+            .count();
+    }
+
+    assert_eq!(sent, 5);
+}
+
+#[test]
+fn connect_timeout() {
+    let (_server_config, mut client_config) = test_config();
+    client_config.server_not_responding_timeout = None;
+    client_config.connect_timeout = Some(Tick { value: 10.0 });
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    for _ in 0..12 {
+        assert!(client.tick());
+    }
+    assert_eq!(
+        client.state,
+        ClientState::Disconnected(DisconnectReason::Timeout)
+    );
+    assert!(!client.tick());
+}
+
+#[test]
+fn challenges_expire() {
+    let hub = VirtualHub::new();
+    let mut server_config = test_server_config(&hub);
+    server_config.handshake_timeout = Tick { value: 5.0 };
+    let server_addr = server_config.addr;
+    let mut server = UnetServer::from_config(server_config).unwrap();
+
+    let client = hub.endpoint(test_client_addr(0)).unwrap();
+    let receive = || {
+        let mut buf = [0; 128];
+        let (n, _) = client.recv_from(&mut buf).unwrap();
+        Packet::from_bytes(&buf[..n]).unwrap()
+    };
+
+    let connection_request = Packet::ConnectionRequest(ConnectionRequest::new(UnetId(1)));
+    client
+        .send_to(&connection_request.as_bytes(), server_addr)
+        .unwrap();
+    server.tick();
+    let Packet::ChallengeRequest(challenge_request) = receive() else {
+        panic!("Expected a ChallengeRequest");
+    };
+
+    // Nothing is kept for a client that hasn't answered
+    assert_eq!(server.connection_count(), 0);
+
+    for _ in 0..10 {
+        server.tick();
+    }
+    let late = ChallengeResponse::new(UnetId(1), 0, challenge_request.cookie);
+    client
+        .send_to(&Packet::ChallengeResponse(late).as_bytes(), server_addr)
+        .unwrap();
+    server.tick();
+    let Packet::ChallengeRequest(challenge_request) = receive() else {
+        panic!("Expected a fresh ChallengeRequest");
+    };
+    assert_eq!(server.connection_count(), 0);
+
+    let response = ChallengeResponse::new(UnetId(1), 0, challenge_request.cookie);
+    client
+        .send_to(&Packet::ChallengeResponse(response).as_bytes(), server_addr)
+        .unwrap();
+    server.tick();
+    assert!(matches!(receive(), Packet::KeepAlive(_)));
+    assert_eq!(server.connection_count(), 1);
+}
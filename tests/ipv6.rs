@@ -0,0 +1,60 @@
+use std::net::SocketAddr;
+use std::thread::sleep;
+use std::time::Duration;
+use unet::client::{ClientState, UnetClient};
+use unet::config::client::ClientConfig;
+use unet::config::server::ServerConfig;
+use unet::server::UnetServer;
+
+fn handshake(server: &mut UnetServer, client: &mut UnetClient) {
+    for _ in 0..50 {
+        client.tick();
+        sleep(Duration::from_millis(2));
+        server.tick();
+        sleep(Duration::from_millis(2));
+        if client.state == ClientState::Connected {
+            return;
+        }
+    }
+}
+
+#[test]
+fn ipv6_server() {
+    let mut server_config = ServerConfig::new();
+    server_config.addr = "[::1]:0".parse().unwrap();
+    let mut server = UnetServer::from_config(server_config).unwrap();
+
+    let mut client_config = ClientConfig::new();
+    client_config.target = server.local_addr().unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+    assert!(client.local_addr().unwrap().is_ipv6());
+
+    handshake(&mut server, &mut client);
+    assert_eq!(client.state, ClientState::Connected);
+}
+
+#[test]
+fn dual_stack_server() {
+    let mut server_config = ServerConfig::new();
+    server_config.addr = "[::]:0".parse().unwrap();
+    server_config.dual_stack = true;
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let port = server.local_addr().unwrap().port();
+
+    let mut client_config = ClientConfig::new();
+    client_config.target = SocketAddr::new("127.0.0.1".parse().unwrap(), port);
+    let mut client = UnetClient::from_config(client_config).unwrap();
+    assert!(client.local_addr().unwrap().is_ipv4());
+
+    handshake(&mut server, &mut client);
+    assert_eq!(client.state, ClientState::Connected);
+
+    let connection = server.connections.iter().flatten().next().unwrap();
+    assert_eq!(
+        connection.connection_identifier.addr,
+        SocketAddr::new(
+            "127.0.0.1".parse().unwrap(),
+            client.local_addr().unwrap().port()
+        )
+    );
+}
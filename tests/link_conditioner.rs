@@ -0,0 +1,53 @@
+use std::time::Duration;
+use unet::client::{ClientState, UnetClient};
+use unet::clock::ManualClock;
+use unet::config::test::conditioned_test_config;
+use unet::network::link_conditioner::LinkConditionerConfig;
+use unet::server::UnetServer;
+
+#[test]
+fn link_conditioner_latency() {
+    let clock = ManualClock::new();
+    let mut link = LinkConditionerConfig::new();
+    link.latency = Duration::from_millis(100);
+    let (server_config, client_config) = conditioned_test_config(clock.clone(), link, link, 0);
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    client.tick(); // Client sends ConnectionRequest
+
+    // The server only answers once the request arrives, the answer takes just as long back
+    clock.advance(Duration::from_millis(99));
+    server.tick();
+    clock.advance(Duration::from_millis(1));
+    server.tick();
+
+    clock.advance(Duration::from_millis(99));
+    client.tick();
+    assert_eq!(client.state, ClientState::SendingConnectionRequest);
+
+    clock.advance(Duration::from_millis(1));
+    client.tick();
+    assert_eq!(client.state, ClientState::SendingConnectionResponse);
+}
+
+#[test]
+fn link_conditioner_handshake_over_lossy_link() {
+    let clock = ManualClock::new();
+    let mut link = LinkConditionerConfig::new();
+    link.latency = Duration::from_millis(40);
+    link.jitter = Duration::from_millis(20);
+    link.loss = 20.0;
+    let (server_config, client_config) = conditioned_test_config(clock.clone(), link, link, 1234);
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    while clock.elapsed() < Duration::from_secs(30) {
+        server.update();
+        assert!(client.update());
+    }
+
+    assert_eq!(client.state, ClientState::Connected);
+}
@@ -0,0 +1,25 @@
+use std::time::{Duration, Instant};
+use unet::client::{ClientState, UnetClient};
+use unet::clock::ManualClock;
+use unet::config::test::test_config;
+use unet::server::UnetServer;
+
+#[test]
+fn manual_clock() {
+    let (mut server_config, mut client_config) = test_config();
+    let clock = ManualClock::new();
+    server_config.clock = Some(Box::new(clock.clone()));
+    client_config.clock = Some(Box::new(clock.clone()));
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    let wall_clock_start = Instant::now();
+    while clock.elapsed() < Duration::from_secs(60) {
+        server.update();
+        assert!(client.update());
+    }
+
+    assert_eq!(client.state, ClientState::Connected);
+    assert!(wall_clock_start.elapsed() < Duration::from_secs(10));
+}
@@ -0,0 +1,60 @@
+use unet::client::ClientState;
+use unet::config::test::{connect_clients, multi_client_test_config, tick_all};
+use unet::packet::disconnect::DisconnectReason;
+use unet::server::UnetServer;
+
+#[test]
+fn max_connections() {
+    let (mut server_config, client_configs) = multi_client_test_config(4);
+    server_config.max_connections = 2;
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut clients = connect_clients(client_configs);
+
+    tick_all(&mut server, &mut clients[..3], 4);
+    assert_eq!(clients[0].state, ClientState::Connected);
+    assert_eq!(clients[1].state, ClientState::Connected);
+    assert_eq!(
+        clients[2].state,
+        ClientState::Disconnected(DisconnectReason::ServerFull)
+    );
+    assert_eq!(server.connection_count(), 2);
+    assert_eq!(server.max_connections(), 2);
+
+    server.set_max_connections(3, false);
+    tick_all(&mut server, &mut clients[3..], 4);
+    assert_eq!(clients[3].state, ClientState::Connected);
+    assert_eq!(server.connection_count(), 3);
+    assert_eq!(server.max_connections(), 3);
+}
+
+#[test]
+fn lowering_max_connections() {
+    let (server_config, client_configs) = multi_client_test_config(3);
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut clients = connect_clients(client_configs);
+
+    tick_all(&mut server, &mut clients, 4);
+    assert_eq!(server.connection_count(), 3);
+
+    // Existing connections stay
+    server.set_max_connections(2, false);
+    tick_all(&mut server, &mut clients, 4);
+    assert_eq!(server.connection_count(), 3);
+    for client in &clients {
+        assert_eq!(client.state, ClientState::Connected);
+    }
+
+    // Until we drain them
+    server.set_max_connections(1, true);
+    tick_all(&mut server, &mut clients, 4);
+    assert_eq!(server.connection_count(), 1);
+    assert_eq!(clients[0].state, ClientState::Connected);
+    assert_eq!(
+        clients[1].state,
+        ClientState::Disconnected(DisconnectReason::ServerFull)
+    );
+    assert_eq!(
+        clients[2].state,
+        ClientState::Disconnected(DisconnectReason::ServerFull)
+    );
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+use unet::client::{ClientState, UnetClient};
+use unet::clock::ManualClock;
+use unet::config::test::{conditioned_test_config, run, test_client_addr};
+use unet::mtu::MtuConfig;
+use unet::network::link_conditioner::LinkConditionerConfig;
+use unet::server::connection::ConnectionIdentifier;
+use unet::server::UnetServer;
+use unet::BUF_SIZE;
+
+#[test]
+fn discovers_mtu_in_both_directions() {
+    let clock = ManualClock::new();
+    let mut client_to_server = LinkConditionerConfig::new();
+    client_to_server.mtu = Some(1300);
+    let mut server_to_client = LinkConditionerConfig::new();
+    server_to_client.mtu = Some(1000);
+    let (mut server_config, mut client_config) =
+        conditioned_test_config(clock.clone(), client_to_server, server_to_client, 0);
+    server_config.mtu_discovery = Some(MtuConfig::new());
+    client_config.mtu_discovery = Some(MtuConfig::new());
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    assert_eq!(client.mtu(), BUF_SIZE);
+
+    run(&clock, &mut server, &mut client, Duration::from_secs(10));
+    assert_eq!(client.state, ClientState::Connected);
+    let connection_identifier =
+        ConnectionIdentifier::new(client.session_id().unwrap(), test_client_addr(0));
+    assert_eq!(client.mtu(), 1280);
+    assert_eq!(server.mtu(connection_identifier), Some(960));
+}
+
+#[test]
+fn no_mtu_discovery_by_default() {
+    let clock = ManualClock::new();
+    let link = LinkConditionerConfig::new();
+    let (server_config, client_config) = conditioned_test_config(clock.clone(), link, link, 0);
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    run(&clock, &mut server, &mut client, Duration::from_secs(5));
+    assert_eq!(client.state, ClientState::Connected);
+    let connection_identifier =
+        ConnectionIdentifier::new(client.session_id().unwrap(), test_client_addr(0));
+    assert_eq!(client.mtu(), BUF_SIZE);
+    assert_eq!(server.mtu(connection_identifier), Some(BUF_SIZE));
+}
@@ -0,0 +1,74 @@
+use unet::client::ClientState;
+use unet::config::test::{connect_clients, multi_client_test_config, tick_all};
+use unet::packet::data::Data;
+use unet::packet::disconnect::DisconnectReason;
+use unet::packet::Packet;
+use unet::server::UnetServer;
+use unet::MAX_CONNECTIONS;
+
+#[test]
+fn multiple_clients_have_distinct_connections() {
+    let (server_config, client_configs) = multi_client_test_config(3);
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut clients = connect_clients(client_configs);
+
+    tick_all(&mut server, &mut clients, 4);
+
+    for client in &clients {
+        assert_eq!(client.state, ClientState::Connected);
+    }
+
+    let mut addrs: Vec<_> = server
+        .connections
+        .iter()
+        .flatten()
+        .map(|connection| connection.connection_identifier.addr)
+        .collect();
+    addrs.sort();
+    addrs.dedup();
+    assert_eq!(addrs.len(), 3);
+}
+
+#[test]
+fn server_full() {
+    let (server_config, client_configs) = multi_client_test_config(MAX_CONNECTIONS + 1);
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut clients = connect_clients(client_configs);
+
+    tick_all(&mut server, &mut clients, 4);
+
+    let (last, rest) = clients.split_last().unwrap();
+    for client in rest {
+        assert_eq!(client.state, ClientState::Connected);
+    }
+    assert_eq!(
+        last.state,
+        ClientState::Disconnected(DisconnectReason::ServerFull)
+    );
+}
+
+#[test]
+fn only_spamming_client_is_kicked() {
+    let (server_config, client_configs) = multi_client_test_config(2);
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut clients = connect_clients(client_configs);
+
+    tick_all(&mut server, &mut clients, 4);
+
+    for _ in 0..30 {
+        let spammer = &mut clients[0];
+        for i in 0..10 {
+            spammer.send(Packet::Data(Data::new(spammer.id, i)));
+        }
+        for client in clients.iter_mut() {
+            client.tick();
+        }
+        server.tick();
+    }
+
+    assert_eq!(
+        clients[0].state,
+        ClientState::Disconnected(DisconnectReason::Spam)
+    );
+    assert_eq!(clients[1].state, ClientState::Connected);
+}
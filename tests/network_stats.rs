@@ -0,0 +1,62 @@
+use std::time::Duration;
+use unet::client::{ClientState, UnetClient};
+use unet::clock::ManualClock;
+use unet::config::test::conditioned_test_config;
+use unet::network::link_conditioner::LinkConditionerConfig;
+use unet::server::UnetServer;
+
+#[test]
+fn network_stats() {
+    let clock = ManualClock::new();
+    let mut link = LinkConditionerConfig::new();
+    link.latency = Duration::from_millis(50);
+    let (server_config, client_config) = conditioned_test_config(clock.clone(), link, link, 0);
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    while clock.elapsed() < Duration::from_secs(10) {
+        server.update();
+        assert!(client.update());
+    }
+    assert_eq!(client.state, ClientState::Connected);
+
+    // Packets are only picked up once per tick, so the measured RTT can be up to a tick longer
+    let client_stats = client.stats();
+    assert!(client_stats.rtt >= Duration::from_millis(100));
+    assert!(client_stats.rtt <= Duration::from_millis(150));
+    assert_eq!(client_stats.packet_loss, 0.0);
+    assert!(client_stats.packets_sent > 0);
+    assert!(client_stats.packets_received > 0);
+    assert!(client_stats.bytes_sent_per_second > 0.0);
+    assert!(client_stats.bytes_received_per_second > 0.0);
+
+    let connection = server.connections.iter().flatten().next().unwrap();
+    let server_stats = server.stats(connection.connection_identifier).unwrap();
+    assert!(server_stats.rtt >= Duration::from_millis(100));
+    assert!(server_stats.rtt <= Duration::from_millis(150));
+    assert_eq!(server_stats.packet_loss, 0.0);
+    assert!(server_stats.packets_received > 0);
+}
+
+#[test]
+fn network_stats_packet_loss() {
+    let clock = ManualClock::new();
+    let mut link = LinkConditionerConfig::new();
+    link.latency = Duration::from_millis(20);
+    link.loss = 30.0;
+    let (server_config, client_config) = conditioned_test_config(clock.clone(), link, link, 99);
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    while clock.elapsed() < Duration::from_secs(30) {
+        server.update();
+        client.update();
+    }
+
+    // A ping is lost when either it or its pong is
+    let packet_loss = client.stats().packet_loss;
+    assert!(packet_loss > 20.0, "{packet_loss}");
+    assert!(packet_loss < 80.0, "{packet_loss}");
+}
@@ -0,0 +1,95 @@
+use std::net::SocketAddr;
+use unet::client::{ClientState, UnetClient};
+use unet::config::client::ClientConfig;
+use unet::config::test::{test_client_addr, test_server_config};
+use unet::network::{Transport, VirtualHub};
+use unet::packet::connection_request::ConnectionRequest;
+use unet::packet::data::Data;
+use unet::packet::keep_alive::KeepAlive;
+use unet::packet::{Packet, UnetId};
+use unet::server::rate_limit::RateLimitConfig;
+use unet::server::UnetServer;
+
+#[test]
+fn handshake_flood_from_one_ip_is_limited() {
+    let hub = VirtualHub::new();
+    let mut server_config = test_server_config(&hub);
+    server_config.rate_limit = Some(RateLimitConfig::new());
+    let server_addr = server_config.addr;
+    let mut server = UnetServer::from_config(server_config).unwrap();
+
+    let flooder = hub.endpoint(test_client_addr(0)).unwrap();
+    for i in 0..100 {
+        let packet = Packet::ConnectionRequest(ConnectionRequest::new(UnetId(i)));
+        flooder.send_to(&packet.as_bytes(), server_addr).unwrap();
+    }
+    server.tick();
+
+    let mut challenges = 0;
+    let mut buf = [0; 128];
+    while let Ok((n, _)) = flooder.recv_from(&mut buf) {
+        if let Some(Packet::ChallengeRequest(_)) = Packet::from_bytes(&buf[..n]) {
+            challenges += 1;
+        }
+    }
+    assert_eq!(challenges, 20);
+    assert_eq!(server.connection_count(), 0);
+    let stats = server.rate_limit_stats().unwrap();
+    assert_eq!(stats.handshake_packets_dropped, 80);
+    assert_eq!(stats.unknown_packets_dropped, 0);
+
+    // Everyone else can still get in
+    let mut client_config = ClientConfig::new();
+    client_config.transport = Some(Box::new(hub.endpoint(test_client_addr(1)).unwrap()));
+    let mut client = UnetClient::from_config(client_config).unwrap();
+    for _ in 0..3 {
+        client.tick();
+        server.tick();
+    }
+    assert_eq!(client.state, ClientState::Connected);
+}
+
+#[test]
+fn unknown_traffic_is_limited_but_connections_are_not() {
+    let hub = VirtualHub::new();
+    let mut server_config = test_server_config(&hub);
+    server_config.rate_limit = Some(RateLimitConfig::new());
+    server_config.spam_policy = None;
+    let server_addr = server_config.addr;
+    let mut server = UnetServer::from_config(server_config).unwrap();
+
+    let mut client_config = ClientConfig::new();
+    client_config.transport = Some(Box::new(hub.endpoint(test_client_addr(0)).unwrap()));
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    for _ in 0..3 {
+        client.tick();
+        server.tick();
+    }
+    assert_eq!(client.state, ClientState::Connected);
+
+    for i in 0..100 {
+        client.send(Packet::Data(Data::new(client.id, i)));
+    }
+    client.tick();
+    server.tick();
+    assert_eq!(
+        server.rate_limit_stats().unwrap().unknown_packets_dropped,
+        0
+    );
+
+    // Same IP, but not a connection
+    let stranger_addr = SocketAddr::new(test_client_addr(0).ip(), 20001);
+    let stranger = hub.endpoint(stranger_addr).unwrap();
+    let keep_alive = Packet::KeepAlive(KeepAlive::new(UnetId(1234)));
+    for _ in 0..15 {
+        stranger
+            .send_to(&keep_alive.as_bytes(), server_addr)
+            .unwrap();
+    }
+    client.tick();
+    server.tick();
+    let stats = server.rate_limit_stats().unwrap();
+    assert_eq!(stats.unknown_packets_dropped, 5);
+    assert_eq!(client.state, ClientState::Connected);
+}
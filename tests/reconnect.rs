@@ -0,0 +1,209 @@
+use std::time::Duration;
+use unet::client::event::ClientEvent;
+use unet::client::reconnect::ReconnectPolicy;
+use unet::client::{ClientState, UnetClient};
+use unet::config::client::ClientConfig;
+use unet::config::test::{test_client_addr, test_config, test_server};
+use unet::network::VirtualHub;
+use unet::packet::disconnect::DisconnectReason;
+use unet::server::connection::ConnectionIdentifier;
+use unet::server::UnetServer;
+use unet::tick::Tick;
+
+fn policy() -> ReconnectPolicy {
+    let mut policy = ReconnectPolicy::new();
+    policy.initial_delay = Duration::from_millis(100);
+    policy.jitter = 0.0;
+    policy
+}
+
+fn events(client: &mut UnetClient) -> Vec<ClientEvent> {
+    let mut events = vec![];
+    while let Some(event) = client.poll_event() {
+        events.push(event);
+    }
+    events
+}
+
+#[test]
+fn reconnects_after_server_restart() {
+    let hub = VirtualHub::new();
+    let mut server = test_server(&hub);
+
+    let mut client_config = ClientConfig::new();
+    client_config.transport = Some(Box::new(hub.endpoint(test_client_addr(0)).unwrap()));
+    client_config.server_not_responding_timeout = Some(Tick { value: 10.0 });
+    client_config.reconnect = Some(policy());
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    for _ in 0..3 {
+        client.tick();
+        server.tick();
+    }
+    assert_eq!(client.state, ClientState::Connected);
+
+    drop(server);
+    for _ in 0..12 {
+        assert!(client.tick());
+    }
+    assert_eq!(
+        client.state,
+        ClientState::Disconnected(DisconnectReason::Timeout)
+    );
+
+    let mut server = test_server(&hub);
+    for _ in 0..10 {
+        assert!(client.tick());
+        server.tick();
+    }
+    assert_eq!(client.state, ClientState::Connected);
+    assert_eq!(
+        events(&mut client),
+        vec![
+            ClientEvent::ReconnectScheduled {
+                attempt: 1,
+                delay: Duration::from_millis(100),
+                reason: DisconnectReason::Timeout
+            },
+            ClientEvent::Reconnecting { attempt: 1 },
+            ClientEvent::Reconnected { attempt: 1 },
+        ]
+    );
+}
+
+#[test]
+fn gives_up_after_max_attempts() {
+    let hub = VirtualHub::new();
+    let mut client_config = ClientConfig::new();
+    client_config.transport = Some(Box::new(hub.endpoint(test_client_addr(0)).unwrap()));
+    client_config.server_not_responding_timeout = Some(Tick { value: 2.0 });
+    let mut policy = policy();
+    policy.max_attempts = Some(2);
+    client_config.reconnect = Some(policy);
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    let mut ticks = 0;
+    while client.tick() {
+        ticks += 1;
+        assert!(ticks < 100);
+    }
+
+    assert_eq!(
+        events(&mut client),
+        vec![
+            ClientEvent::ReconnectScheduled {
+                attempt: 1,
+                delay: Duration::from_millis(100),
+                reason: DisconnectReason::Timeout
+            },
+            ClientEvent::Reconnecting { attempt: 1 },
+            ClientEvent::ReconnectScheduled {
+                attempt: 2,
+                delay: Duration::from_millis(200),
+                reason: DisconnectReason::Timeout
+            },
+            ClientEvent::Reconnecting { attempt: 2 },
+            ClientEvent::ReconnectFailed {
+                reason: DisconnectReason::Timeout
+            },
+        ]
+    );
+}
+
+#[test]
+fn server_full_is_not_retried() {
+    let (mut server_config, mut client_config) = test_config();
+    server_config.max_connections = 0;
+    client_config.reconnect = Some(policy());
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    client.tick();
+    server.tick();
+    client.tick();
+    assert_eq!(
+        client.state,
+        ClientState::Disconnected(DisconnectReason::ServerFull)
+    );
+    assert!(!client.tick());
+    assert_eq!(
+        events(&mut client),
+        vec![ClientEvent::ReconnectFailed {
+            reason: DisconnectReason::ServerFull
+        }]
+    );
+}
+
+#[test]
+fn server_full_can_be_retried() {
+    let (mut server_config, mut client_config) = test_config();
+    server_config.max_connections = 0;
+    let mut policy = policy();
+    policy.retryable.push(DisconnectReason::ServerFull);
+    client_config.reconnect = Some(policy);
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    client.tick();
+    server.tick();
+    client.tick();
+    client.tick();
+    server.set_max_connections(1, false);
+    for _ in 0..10 {
+        assert!(client.tick());
+        server.tick();
+    }
+
+    assert_eq!(client.state, ClientState::Connected);
+    assert_eq!(
+        events(&mut client).last(),
+        Some(&ClientEvent::Reconnected { attempt: 1 })
+    );
+}
+
+#[test]
+fn reconnects_before_server_drops_old_session() {
+    let (mut server_config, mut client_config) = test_config();
+    server_config.max_connections = 1;
+    client_config.server_not_responding_timeout = Some(Tick { value: 10.0 });
+    client_config.reconnect = Some(policy());
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+    for _ in 0..3 {
+        client.tick();
+        server.tick();
+    }
+    assert_eq!(client.state, ClientState::Connected);
+    let old_session_id = client.session_id().unwrap();
+
+    // Our side gives up, the server hasn't noticed anything yet
+    for _ in 0..12 {
+        assert!(client.tick());
+    }
+    assert_eq!(
+        client.state,
+        ClientState::Disconnected(DisconnectReason::Timeout)
+    );
+
+    for _ in 0..10 {
+        assert!(client.tick());
+        server.tick();
+    }
+    assert_eq!(client.state, ClientState::Connected);
+    let session_id = client.session_id().unwrap();
+    assert_ne!(session_id, old_session_id);
+    assert_eq!(server.connection_count(), 1);
+
+    // Well past the server's timeout, so the new session really works both ways
+    for _ in 0..200 {
+        assert!(client.tick());
+        server.tick();
+    }
+    assert_eq!(client.state, ClientState::Connected);
+    assert_eq!(client.session_id(), Some(session_id));
+    let connection_identifier = ConnectionIdentifier::new(session_id, test_client_addr(0));
+    assert!(server.stats(connection_identifier).is_some());
+}
@@ -0,0 +1,176 @@
+use unet::client::{ClientState, UnetClient};
+use unet::config::server::ServerConfig;
+use unet::config::test::{connect_clients, multi_client_test_config, tick_all};
+use unet::packet::disconnect::DisconnectReason;
+use unet::server::accept::PendingConnection;
+use unet::server::event::ServerEvent;
+use unet::server::reserved_slots::ReservedSlotsConfig;
+use unet::server::waiting_queue::WaitingQueueConfig;
+use unet::server::UnetServer;
+
+/// Every client after the first `regular` ones has priority 1, which the server takes on trust.
+fn setup(
+    server_config: impl FnOnce(&mut ServerConfig),
+    clients: usize,
+    regular: usize,
+) -> (UnetServer, Vec<UnetClient>) {
+    let (mut config, mut client_configs) = multi_client_test_config(clients);
+    config.accept_hook = Some(Box::new(|_: &PendingConnection| Ok(())));
+    server_config(&mut config);
+    for client_config in &mut client_configs[regular..] {
+        client_config.priority = 1;
+    }
+
+    let server = UnetServer::from_config(config).unwrap();
+    (server, connect_clients(client_configs))
+}
+
+#[test]
+fn reserved_slots_are_for_priority_clients() {
+    let (mut server, clients) = setup(
+        |config| {
+            config.max_connections = 2;
+            config.reserved_slots = Some(ReservedSlotsConfig::new());
+        },
+        3,
+        2,
+    );
+    let [mut first, mut second, mut admin]: [UnetClient; 3] = clients.try_into().unwrap();
+
+    tick_all(&mut server, &mut [&mut first], 5);
+    assert_eq!(first.state, ClientState::Connected);
+
+    tick_all(&mut server, &mut [&mut first, &mut second], 5);
+    assert_eq!(
+        second.state,
+        ClientState::Disconnected(DisconnectReason::ServerFull)
+    );
+
+    tick_all(&mut server, &mut [&mut first, &mut admin], 5);
+    assert_eq!(admin.state, ClientState::Connected);
+    assert_eq!(server.connection_count(), 2);
+    let connection = server
+        .connections
+        .iter()
+        .flatten()
+        .find(|connection| connection.connection_identifier.id == admin.session_id().unwrap())
+        .unwrap();
+    assert_eq!(connection.priority, 1);
+}
+
+#[test]
+fn priority_clients_skip_the_queue() {
+    let (mut server, clients) = setup(
+        |config| {
+            config.max_connections = 2;
+            config.reserved_slots = Some(ReservedSlotsConfig::new());
+            config.waiting_queue = Some(WaitingQueueConfig::new());
+        },
+        3,
+        2,
+    );
+    let [mut first, mut second, mut admin]: [UnetClient; 3] = clients.try_into().unwrap();
+
+    tick_all(&mut server, &mut [&mut first], 5);
+    tick_all(&mut server, &mut [&mut first, &mut second], 5);
+    assert_eq!(second.state, ClientState::Queued);
+
+    tick_all(&mut server, &mut [&mut first, &mut second, &mut admin], 5);
+    assert_eq!(admin.state, ClientState::Connected);
+    assert_eq!(second.state, ClientState::Queued);
+    assert_eq!(server.waiting_count(), 1);
+}
+
+#[test]
+fn priority_is_ignored_without_accept_hook() {
+    let (mut server, clients) = setup(
+        |config| {
+            config.max_connections = 2;
+            config.reserved_slots = Some(ReservedSlotsConfig::new());
+            config.accept_hook = None;
+        },
+        2,
+        0,
+    );
+    let [mut first, mut second]: [UnetClient; 2] = clients.try_into().unwrap();
+
+    tick_all(&mut server, &mut [&mut first], 5);
+    assert_eq!(first.state, ClientState::Connected);
+    let connection = server.connections.iter().flatten().next().unwrap();
+    assert_eq!(connection.priority, 0);
+
+    tick_all(&mut server, &mut [&mut first, &mut second], 5);
+    assert_eq!(
+        second.state,
+        ClientState::Disconnected(DisconnectReason::ServerFull)
+    );
+}
+
+#[test]
+fn eviction() {
+    let (mut server, clients) = setup(
+        |config| {
+            config.max_connections = 1;
+            let mut reserved_slots = ReservedSlotsConfig::new();
+            reserved_slots.slots = 0;
+            reserved_slots.evict = true;
+            config.reserved_slots = Some(reserved_slots);
+        },
+        3,
+        1,
+    );
+    let [mut first, mut admin, mut other_admin]: [UnetClient; 3] = clients.try_into().unwrap();
+
+    tick_all(&mut server, &mut [&mut first], 5);
+    let evicted = server.connections[0]
+        .as_ref()
+        .unwrap()
+        .connection_identifier;
+
+    tick_all(&mut server, &mut [&mut first, &mut admin], 5);
+    assert_eq!(
+        first.state,
+        ClientState::Disconnected(DisconnectReason::Evicted)
+    );
+    assert_eq!(admin.state, ClientState::Connected);
+    assert_eq!(
+        server.poll_event(),
+        Some(ServerEvent::Evicted {
+            connection_identifier: evicted
+        })
+    );
+
+    // Nobody ranks lower than the admin now
+    tick_all(&mut server, &mut [&mut admin, &mut other_admin], 5);
+    assert_eq!(admin.state, ClientState::Connected);
+    assert_eq!(
+        other_admin.state,
+        ClientState::Disconnected(DisconnectReason::ServerFull)
+    );
+}
+
+#[test]
+fn no_eviction_without_accept_hook() {
+    let (mut server, clients) = setup(
+        |config| {
+            config.max_connections = 1;
+            let mut reserved_slots = ReservedSlotsConfig::new();
+            reserved_slots.slots = 0;
+            reserved_slots.evict = true;
+            config.reserved_slots = Some(reserved_slots);
+            config.accept_hook = None;
+        },
+        2,
+        1,
+    );
+    let [mut first, mut admin]: [UnetClient; 2] = clients.try_into().unwrap();
+
+    tick_all(&mut server, &mut [&mut first], 5);
+    tick_all(&mut server, &mut [&mut first, &mut admin], 5);
+    assert_eq!(first.state, ClientState::Connected);
+    assert_eq!(
+        admin.state,
+        ClientState::Disconnected(DisconnectReason::ServerFull)
+    );
+    assert_eq!(server.poll_event(), None);
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+use unet::client::{ClientState, UnetClient};
+use unet::clock::ManualClock;
+use unet::config::test::{conditioned_test_config, run, test_client_addr, test_config};
+use unet::congestion::{CongestionConfig, CongestionMode};
+use unet::network::link_conditioner::LinkConditionerConfig;
+use unet::packet::data::Data;
+use unet::packet::{Packet, UnetId};
+use unet::server::connection::ConnectionIdentifier;
+use unet::server::UnetServer;
+
+#[test]
+fn queued_packets_are_sent() {
+    let (server_config, client_config) = test_config();
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    for _ in 0..5 {
+        assert!(client.tick());
+        server.tick();
+    }
+    assert_eq!(client.state, ClientState::Connected);
+
+    let connection_identifier = server.connections[0]
+        .as_ref()
+        .unwrap()
+        .connection_identifier;
+    let received = client.stats().packets_received;
+    for i in 0..10 {
+        assert!(server.send(connection_identifier, Packet::Data(Data::new(client.id, i))));
+    }
+    assert_eq!(server.connections[0].as_ref().unwrap().send_queue.len(), 10);
+
+    server.tick();
+    assert!(server.connections[0]
+        .as_ref()
+        .unwrap()
+        .send_queue
+        .is_empty());
+
+    assert!(client.tick());
+    assert!(client.stats().packets_received >= received + 10);
+}
+
+#[test]
+fn sequence_is_per_connection() {
+    let (server_config, client_config) = test_config();
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    for _ in 0..5 {
+        assert!(client.tick());
+        server.tick();
+    }
+    assert_eq!(client.state, ClientState::Connected);
+
+    let connection = server.connections[0].as_ref().unwrap();
+    let connection_identifier = connection.connection_identifier;
+    let sequence = connection.sequence;
+    for i in 0..10 {
+        server.send(connection_identifier, Packet::Data(Data::new(client.id, i)));
+    }
+    server.tick();
+
+    let connection = server.connections[0].as_ref().unwrap();
+    assert!(connection.sequence >= sequence + 10);
+}
+
+#[test]
+fn send_to_unknown_connection() {
+    let (server_config, _) = test_config();
+    let mut server = UnetServer::from_config(server_config).unwrap();
+
+    let connection_identifier = ConnectionIdentifier::new(UnetId(1), test_client_addr(0));
+    assert!(!server.send(connection_identifier, Packet::Data(Data::new(UnetId(1), 0))));
+}
+
+#[test]
+fn congestion_control_throttles_server_send_queue() {
+    let clock = ManualClock::new();
+    let mut link = LinkConditionerConfig::new();
+    link.latency = Duration::from_millis(200);
+    let (mut server_config, client_config) = conditioned_test_config(clock.clone(), link, link, 0);
+    server_config.congestion_control = Some(CongestionConfig::new());
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    run(&clock, &mut server, &mut client, Duration::from_secs(5));
+    assert_eq!(client.state, ClientState::Connected);
+
+    let connection_identifier = server.connections[0]
+        .as_ref()
+        .unwrap()
+        .connection_identifier;
+    assert_eq!(server.allowed_send_rate(connection_identifier), Some(10.0));
+    let connection = server.connections[0].as_ref().unwrap();
+    assert_eq!(
+        connection.congestion.as_ref().unwrap().mode(),
+        CongestionMode::Bad
+    );
+
+    for i in 0..100 {
+        server.send(connection_identifier, Packet::Data(Data::new(client.id, i)));
+    }
+    run(&clock, &mut server, &mut client, Duration::from_secs(2));
+
+    let sent = 100 - server.connections[0].as_ref().unwrap().send_queue.len();
+    assert!((20..=30).contains(&sent), "{sent}");
+}
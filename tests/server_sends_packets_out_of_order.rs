@@ -0,0 +1,77 @@
+use unet::client::{ClientState, UnetClient};
+use unet::config::client::ClientConfig;
+use unet::config::server::ServerConfig;
+use unet::config::test::{test_client_addr, test_config};
+use unet::cookie::Cookie;
+use unet::network::{Transport, VirtualHub};
+use unet::packet::challenge_request::ChallengeRequest;
+use unet::packet::data::Data;
+use unet::packet::keep_alive::KeepAlive;
+use unet::packet::Packet;
+use unet::server::UnetServer;
+
+#[test]
+fn server_sends_packets_out_of_order() {
+    // Stand in for the server, so packets can go out with whatever sequence we like
+    let hub = VirtualHub::new();
+    let server_addr = ServerConfig::new().addr;
+    let server = hub.endpoint(server_addr).unwrap();
+
+    let mut client_config = ClientConfig::new();
+    client_config.target = server_addr;
+    client_config.transport = Some(Box::new(hub.endpoint(test_client_addr(0)).unwrap()));
+    let mut client = UnetClient::from_config(client_config).unwrap();
+    let client_addr = test_client_addr(0);
+
+    let send = |mut packet: Packet, sequence: u64| {
+        packet.set_sequence(sequence);
+        server.send_to(&packet.as_bytes(), client_addr).unwrap();
+    };
+
+    send(
+        Packet::ChallengeRequest(ChallengeRequest::new(client.id, Cookie::default())),
+        0,
+    );
+    assert!(client.tick());
+    send(Packet::KeepAlive(KeepAlive::new(client.id)), 1);
+    assert!(client.tick());
+    assert_eq!(client.state, ClientState::Connected);
+
+    send(Packet::Data(Data::new(client.id, 0)), 5);
+    send(Packet::Data(Data::new(client.id, 1)), 3); // Late, but not seen before
+    send(Packet::Data(Data::new(client.id, 2)), 5); // Duplicate
+    send(Packet::Data(Data::new(client.id, 3)), 3); // Duplicate
+    send(Packet::Data(Data::new(client.id, 4)), 6);
+    assert!(client.tick());
+
+    let stats = client.stats();
+    assert_eq!(stats.packets_received, 7);
+    assert_eq!(stats.packets_late, 1);
+    assert_eq!(stats.packets_duplicated, 2);
+    assert_eq!(stats.packets_dropped, 2);
+
+    send(Packet::Data(Data::new(client.id, 5)), 1000);
+    send(Packet::Data(Data::new(client.id, 6)), 7); // Fell out of the replay window
+    assert!(client.tick());
+
+    let stats = client.stats();
+    assert_eq!(stats.packets_duplicated, 2);
+    assert_eq!(stats.packets_dropped, 3);
+}
+
+#[test]
+fn server_packets_are_sequenced() {
+    let (server_config, client_config) = test_config();
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    for _ in 0..5 {
+        assert!(client.tick());
+        server.tick();
+    }
+    assert_eq!(client.state, ClientState::Connected);
+
+    let connection = server.connections[0].as_ref().unwrap();
+    assert!(connection.sequence >= 1); // The accepting KeepAlive
+    assert_eq!(client.stats().packets_dropped, 0);
+}
@@ -0,0 +1,115 @@
+use std::time::Duration;
+use unet::client::{ClientState, UnetClient};
+use unet::clock::ManualClock;
+use unet::config::test::{
+    conditioned_test_config, connect_clients, multi_client_test_config, tick_all,
+};
+use unet::congestion::CongestionConfig;
+use unet::network::link_conditioner::LinkConditionerConfig;
+use unet::packet::data::Data;
+use unet::packet::disconnect::DisconnectReason;
+use unet::packet::Packet;
+use unet::server::waiting_queue::WaitingQueueConfig;
+use unet::server::UnetServer;
+
+#[test]
+fn shutdown_notifies_everyone() {
+    let clock = ManualClock::new();
+    let (mut server_config, client_configs) = multi_client_test_config(3);
+    server_config.clock = Some(Box::new(clock.clone()));
+    server_config.max_connections = 1;
+    server_config.waiting_queue = Some(WaitingQueueConfig::new());
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut clients = connect_clients(client_configs);
+    let mut latecomer = clients.pop().unwrap();
+
+    tick_all(&mut server, &mut clients[..1], 5);
+    tick_all(&mut server, &mut clients, 5);
+    assert_eq!(clients[0].state, ClientState::Connected);
+    assert_eq!(clients[1].state, ClientState::Queued);
+
+    server.shutdown();
+    assert!(server.is_shutting_down());
+    assert_eq!(server.connection_count(), 0);
+    assert_eq!(server.waiting_count(), 0);
+
+    for client in &mut clients {
+        client.tick();
+        assert_eq!(
+            client.state,
+            ClientState::Disconnected(DisconnectReason::ServerShutdown)
+        );
+    }
+
+    // Nobody new gets in
+    tick_all(&mut server, &mut [&mut latecomer], 5);
+    assert_eq!(
+        latecomer.state,
+        ClientState::Disconnected(DisconnectReason::ServerShutdown)
+    );
+    assert_eq!(server.connection_count(), 0);
+}
+
+#[test]
+fn shutdown_flushes_send_queues() {
+    let clock = ManualClock::new();
+    let (mut server_config, client_configs) = multi_client_test_config(1);
+    server_config.clock = Some(Box::new(clock.clone()));
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut clients = connect_clients(client_configs);
+    tick_all(&mut server, &mut clients, 5);
+
+    let client = &mut clients[0];
+    let connection_identifier = server.connections[0]
+        .as_ref()
+        .unwrap()
+        .connection_identifier;
+    let received = client.stats().packets_received;
+    for i in 0..10 {
+        server.send(connection_identifier, Packet::Data(Data::new(client.id, i)));
+    }
+
+    server.shutdown();
+    client.tick();
+    assert!(client.stats().packets_received >= received + 10);
+    assert_eq!(
+        client.state,
+        ClientState::Disconnected(DisconnectReason::ServerShutdown)
+    );
+}
+
+#[test]
+fn shutdown_has_a_deadline() {
+    let clock = ManualClock::new();
+    let mut link = LinkConditionerConfig::new();
+    link.latency = Duration::from_millis(200);
+    let (mut server_config, client_config) = conditioned_test_config(clock.clone(), link, link, 0);
+    server_config.congestion_control = Some(CongestionConfig::new());
+    server_config.shutdown_timeout = Duration::from_secs(2);
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    let end = clock.elapsed() + Duration::from_secs(5);
+    while clock.elapsed() < end {
+        server.update();
+        client.update();
+    }
+    assert_eq!(client.state, ClientState::Connected);
+
+    // Throttled to 10 packets a second, way more than fits in the deadline
+    let connection_identifier = server.connections[0]
+        .as_ref()
+        .unwrap()
+        .connection_identifier;
+    for i in 0..1000 {
+        server.send(connection_identifier, Packet::Data(Data::new(client.id, i)));
+    }
+
+    let started = clock.elapsed();
+    server.shutdown();
+    let took = clock.elapsed() - started;
+    assert!(took >= Duration::from_secs(2), "{took:?}");
+    assert!(took < Duration::from_secs(3), "{took:?}");
+    assert_eq!(server.connection_count(), 0);
+}
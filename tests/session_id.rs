@@ -0,0 +1,68 @@
+use unet::client::{ClientState, UnetClient};
+use unet::config::test::{
+    connect_clients, multi_client_test_config, test_client_addr, test_config,
+};
+use unet::packet::UnetId;
+use unet::server::connection::ConnectionIdentifier;
+use unet::server::UnetServer;
+
+#[test]
+fn server_assigns_session_ids() {
+    let (server_config, mut client_configs) = multi_client_test_config(2);
+    for client_config in &mut client_configs {
+        client_config.id = Some(UnetId(7)); // Both pick the same id
+    }
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut clients = connect_clients(client_configs);
+
+    assert_eq!(clients[0].session_id(), None);
+    for _ in 0..3 {
+        for client in &mut clients {
+            assert!(client.tick());
+        }
+        server.tick();
+    }
+
+    let mut session_ids = vec![];
+    for (i, client) in clients.iter_mut().enumerate() {
+        assert!(client.tick());
+        assert_eq!(client.state, ClientState::Connected);
+        assert_eq!(client.id, UnetId(7));
+
+        let session_id = client.session_id().unwrap();
+        assert_ne!(session_id, client.id);
+        assert!(server
+            .stats(ConnectionIdentifier::new(session_id, test_client_addr(i)))
+            .is_some());
+        // The id the client picked doesn't get it anywhere
+        assert!(server
+            .stats(ConnectionIdentifier::new(client.id, test_client_addr(i)))
+            .is_none());
+        session_ids.push(session_id);
+    }
+    assert_ne!(session_ids[0], session_ids[1]);
+
+    let connection = server.connections.iter().flatten().next().unwrap();
+    assert_eq!(connection.client_id, UnetId(7));
+}
+
+#[test]
+fn session_stays_the_same() {
+    let (server_config, client_config) = test_config();
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    for _ in 0..3 {
+        assert!(client.tick());
+        server.tick();
+    }
+    let session_id = client.session_id().unwrap();
+
+    for _ in 0..200 {
+        assert!(client.tick());
+        server.tick();
+    }
+    assert_eq!(client.state, ClientState::Connected);
+    assert_eq!(client.session_id(), Some(session_id));
+    assert_eq!(server.connection_count(), 1);
+}
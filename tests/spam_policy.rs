@@ -0,0 +1,123 @@
+use std::time::Duration;
+use unet::client::{ClientState, UnetClient};
+use unet::config::test::{test_client_addr, test_config};
+use unet::packet::data::Data;
+use unet::packet::disconnect::DisconnectReason;
+use unet::packet::Packet;
+use unet::server::connection::ConnectionIdentifier;
+use unet::server::event::ServerEvent;
+use unet::server::spam::{SpamAction, SpamPolicy};
+use unet::server::UnetServer;
+
+fn setup(action: SpamAction) -> (UnetServer, UnetClient) {
+    let (mut server_config, client_config) = test_config();
+    let mut policy = SpamPolicy::new();
+    policy.packets_per_second = 20.0; // One packet per tick
+    policy.packet_burst = 10.0;
+    policy.action = action;
+    policy.grace_period = Duration::ZERO;
+    server_config.spam_policy = Some(policy);
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+    for _ in 0..3 {
+        client.tick();
+        server.tick();
+    }
+    client.tick();
+    assert_eq!(client.state, ClientState::Connected);
+
+    (server, client)
+}
+
+fn spam(server: &mut UnetServer, client: &mut UnetClient, packets: i32) {
+    for i in 0..packets {
+        client.send(Packet::Data(Data::new(client.id, i)));
+    }
+    client.tick();
+    server.tick();
+    client.tick();
+}
+
+fn connection_identifier(client: &UnetClient) -> ConnectionIdentifier {
+    ConnectionIdentifier::new(client.session_id().unwrap(), test_client_addr(0))
+}
+
+#[test]
+fn kick() {
+    let (mut server, mut client) = setup(SpamAction::Kick);
+    spam(&mut server, &mut client, 30);
+    assert_eq!(
+        client.state,
+        ClientState::Disconnected(DisconnectReason::Spam)
+    );
+}
+
+#[test]
+fn drop_excess() {
+    let (mut server, mut client) = setup(SpamAction::Drop);
+    let ci = connection_identifier(&client);
+    let dropped_before = server.stats(ci).unwrap().packets_dropped;
+
+    spam(&mut server, &mut client, 30);
+    assert_eq!(client.state, ClientState::Connected);
+    let dropped = server.stats(ci).unwrap().packets_dropped - dropped_before;
+    assert!((15..=25).contains(&dropped), "{dropped}");
+}
+
+#[test]
+fn warn() {
+    let (mut server, mut client) = setup(SpamAction::Warn);
+    let ci = connection_identifier(&client);
+
+    spam(&mut server, &mut client, 30);
+    spam(&mut server, &mut client, 30);
+    assert_eq!(client.state, ClientState::Connected);
+    assert_eq!(server.stats(ci).unwrap().packets_dropped, 0);
+    assert_eq!(
+        server.poll_event(),
+        Some(ServerEvent::Spamming {
+            connection_identifier: ci
+        })
+    );
+    assert_eq!(server.poll_event(), None);
+}
+
+#[test]
+fn throttle() {
+    let (mut server, mut client) = setup(SpamAction::Throttle);
+    let ci = connection_identifier(&client);
+    let received_before = server.stats(ci).unwrap().packets_received;
+
+    // Over the limit, but fits in the throttle queue
+    spam(&mut server, &mut client, 18);
+    for _ in 0..20 {
+        client.tick();
+        server.tick();
+    }
+
+    assert_eq!(client.state, ClientState::Connected);
+    let stats = server.stats(ci).unwrap();
+    assert!(stats.packets_received - received_before >= 18);
+    assert_eq!(stats.packets_dropped, 0);
+}
+
+#[test]
+fn grace_period() {
+    let (mut server_config, client_config) = test_config();
+    let mut policy = SpamPolicy::new();
+    policy.packets_per_second = 20.0;
+    policy.packet_burst = 10.0;
+    policy.grace_period = Duration::from_secs(10);
+    server_config.spam_policy = Some(policy);
+
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let mut client = UnetClient::from_config(client_config).unwrap();
+    for _ in 0..3 {
+        client.tick();
+        server.tick();
+    }
+
+    spam(&mut server, &mut client, 30);
+    assert_eq!(client.state, ClientState::Connected);
+}
@@ -0,0 +1,172 @@
+use unet::client::{ClientState, UnetClient};
+use unet::config::client::ClientConfig;
+use unet::config::server::ServerConfig;
+use unet::config::test::{test_client_addr, test_server, test_server_config};
+use unet::cookie::Cookie;
+use unet::network::{Transport, VirtualHub};
+use unet::packet::challenge_response::ChallengeResponse;
+use unet::packet::connection_request::ConnectionRequest;
+use unet::packet::disconnect::{Disconnect, DisconnectReason};
+use unet::packet::keep_alive::KeepAlive;
+use unet::packet::ping::Ping;
+use unet::packet::{Packet, UnetId};
+use unet::server::UnetServer;
+
+#[test]
+fn spoofed_requests_dont_take_slots() {
+    let hub = VirtualHub::new();
+    let mut server_config = test_server_config(&hub);
+    server_config.max_connections = 4;
+    let server_addr = server_config.addr;
+    let mut server = UnetServer::from_config(server_config).unwrap();
+
+    // Never sees the challenges, like a spoofed source address wouldn't
+    for i in 0..100 {
+        let spoofed = hub.endpoint(test_client_addr(100 + i)).unwrap();
+        let connection_request = ConnectionRequest::new(UnetId(i as u64));
+        spoofed
+            .send_to(
+                &Packet::ConnectionRequest(connection_request).as_bytes(),
+                server_addr,
+            )
+            .unwrap();
+    }
+    server.tick();
+    assert_eq!(server.connection_count(), 0);
+
+    let mut client_config = ClientConfig::new();
+    client_config.transport = Some(Box::new(hub.endpoint(test_client_addr(0)).unwrap()));
+    let mut client = UnetClient::from_config(client_config).unwrap();
+    for _ in 0..3 {
+        assert!(client.tick());
+        server.tick();
+    }
+    assert!(client.tick());
+    assert_eq!(client.state, ClientState::Connected);
+    assert_eq!(server.connection_count(), 1);
+}
+
+#[test]
+fn forged_cookies_are_rejected() {
+    let hub = VirtualHub::new();
+    let mut server = test_server(&hub);
+    let server_addr = ServerConfig::new().addr;
+
+    let attacker = hub.endpoint(test_client_addr(0)).unwrap();
+    let forged = ChallengeResponse::new(UnetId(1), 0, Cookie::default());
+    attacker
+        .send_to(&Packet::ChallengeResponse(forged).as_bytes(), server_addr)
+        .unwrap();
+    server.tick();
+    assert_eq!(server.connection_count(), 0);
+}
+
+#[test]
+fn cookies_are_bound_to_the_address() {
+    let hub = VirtualHub::new();
+    let mut server = test_server(&hub);
+    let server_addr = ServerConfig::new().addr;
+
+    let client = hub.endpoint(test_client_addr(0)).unwrap();
+    let connection_request = Packet::ConnectionRequest(ConnectionRequest::new(UnetId(1)));
+    client
+        .send_to(&connection_request.as_bytes(), server_addr)
+        .unwrap();
+    server.tick();
+
+    let mut buf = [0; 128];
+    let (n, _) = client.recv_from(&mut buf).unwrap();
+    let Some(Packet::ChallengeRequest(challenge_request)) = Packet::from_bytes(&buf[..n]) else {
+        panic!("Expected a ChallengeRequest");
+    };
+
+    // Same id and cookie, but from somewhere else
+    let other = hub.endpoint(test_client_addr(1)).unwrap();
+    let response = ChallengeResponse::new(UnetId(1), 0, challenge_request.cookie);
+    other
+        .send_to(&Packet::ChallengeResponse(response).as_bytes(), server_addr)
+        .unwrap();
+    server.tick();
+    assert_eq!(server.connection_count(), 0);
+}
+
+#[test]
+fn responses_cant_be_replayed() {
+    let hub = VirtualHub::new();
+    let mut server = test_server(&hub);
+    let server_addr = ServerConfig::new().addr;
+    let mut buf = [0; 128];
+
+    let client = hub.endpoint(test_client_addr(0)).unwrap();
+    let connection_request = Packet::ConnectionRequest(ConnectionRequest::new(UnetId(1)));
+    client
+        .send_to(&connection_request.as_bytes(), server_addr)
+        .unwrap();
+    server.tick();
+    let (n, _) = client.recv_from(&mut buf).unwrap();
+    let Some(Packet::ChallengeRequest(challenge_request)) = Packet::from_bytes(&buf[..n]) else {
+        panic!("Expected a ChallengeRequest");
+    };
+    let response = Packet::ChallengeResponse(ChallengeResponse::new(
+        UnetId(1),
+        0,
+        challenge_request.cookie,
+    ));
+    client.send_to(&response.as_bytes(), server_addr).unwrap();
+    server.tick();
+    let (n, _) = client.recv_from(&mut buf).unwrap();
+    let Some(Packet::KeepAlive(keep_alive)) = Packet::from_bytes(&buf[..n]) else {
+        panic!("Expected a KeepAlive");
+    };
+    assert_eq!(server.connection_count(), 1);
+
+    let mut disconnect = Packet::Disconnect(Disconnect::new(
+        keep_alive.header.client_id,
+        DisconnectReason::ConnectionResetByPeer,
+    ));
+    disconnect.set_sequence(1);
+    client.send_to(&disconnect.as_bytes(), server_addr).unwrap();
+    server.tick();
+    assert_eq!(server.connection_count(), 0);
+
+    // Someone who saw the response sends it again while the cookie is still fresh
+    client.send_to(&response.as_bytes(), server_addr).unwrap();
+    server.tick();
+    assert_eq!(server.connection_count(), 0);
+}
+
+#[test]
+fn short_requests_are_dropped() {
+    let hub = VirtualHub::new();
+    let mut server = test_server(&hub);
+    let server_addr = ServerConfig::new().addr;
+
+    let client = hub.endpoint(test_client_addr(0)).unwrap();
+    let connection_request = Packet::ConnectionRequest(ConnectionRequest::new(UnetId(1)));
+    let bytes = connection_request.as_bytes();
+    client.send_to(&bytes[..23], server_addr).unwrap();
+    server.tick();
+
+    let mut buf = [0; 128];
+    assert!(client.recv_from(&mut buf).is_err());
+}
+
+#[test]
+fn short_datagrams_are_dropped() {
+    let hub = VirtualHub::new();
+    let mut server = test_server(&hub);
+    let server_addr = ServerConfig::new().addr;
+
+    let stranger = hub.endpoint(test_client_addr(0)).unwrap();
+    let keep_alive = Packet::KeepAlive(KeepAlive::new(UnetId(1))).as_bytes();
+    let ping = Packet::Ping(Ping::new(UnetId(1), 1)).as_bytes();
+    let datagrams: [&[u8]; 4] = [&[], &[2, 0], &keep_alive[..10], &ping[..ping.len() - 1]];
+    for datagram in datagrams {
+        stranger.send_to(datagram, server_addr).unwrap();
+    }
+    server.tick();
+    assert_eq!(server.connection_count(), 0);
+
+    let mut buf = [0; 128];
+    assert!(stranger.recv_from(&mut buf).is_err());
+}
@@ -0,0 +1,150 @@
+use unet::client::{ClientState, UnetClient};
+use unet::config::client::ClientConfig;
+use unet::config::test::{test_client_addr, test_server_config};
+use unet::cookie::Cookie;
+use unet::network::{Transport, VirtualHub, VirtualNetwork};
+use unet::packet::challenge_request::ChallengeRequest;
+use unet::packet::challenge_response::ChallengeResponse;
+use unet::packet::connection_request::ConnectionRequest;
+use unet::packet::disconnect::{Disconnect, DisconnectReason};
+use unet::packet::keep_alive::KeepAlive;
+use unet::packet::path_challenge::PathChallenge;
+use unet::packet::path_response::PathResponse;
+use unet::packet::queued::Queued;
+use unet::packet::{Packet, UnetId};
+use unet::server::connection::ConnectionIdentifier;
+use unet::server::UnetServer;
+
+/// Packets only the server sends, or that only make sense from a migrating client.
+fn unexpected_packets(id: UnetId) -> [Packet; 4] {
+    [
+        Packet::ChallengeRequest(ChallengeRequest::new(id, Cookie::default())),
+        Packet::Queued(Queued::new(id, 1)),
+        Packet::PathChallenge(PathChallenge::new(id, 1)),
+        Packet::PathResponse(PathResponse::new(id, 1, 1)),
+    ]
+}
+
+fn receive(network: &VirtualNetwork) -> Packet {
+    let mut buf = [0; 512];
+    let (n, _) = network.recv_from(&mut buf).unwrap();
+    Packet::from_bytes(&buf[..n]).unwrap()
+}
+
+#[test]
+fn unexpected_packets_from_strangers_are_ignored() {
+    for connection_migration in [false, true] {
+        let hub = VirtualHub::new();
+        let mut server_config = test_server_config(&hub);
+        server_config.connection_migration = connection_migration;
+        let server_addr = server_config.addr;
+        let mut server = UnetServer::from_config(server_config).unwrap();
+
+        let stranger = hub.endpoint(test_client_addr(0)).unwrap();
+        let disconnect = Packet::Disconnect(Disconnect::new(UnetId(1), DisconnectReason::Timeout));
+        for packet in unexpected_packets(UnetId(1))
+            .into_iter()
+            .chain([disconnect])
+        {
+            stranger.send_to(&packet.as_bytes(), server_addr).unwrap();
+        }
+        // Not a packet kind we know
+        stranger.send_to(&[200; 40], server_addr).unwrap();
+        server.tick();
+        assert_eq!(server.connection_count(), 0);
+    }
+}
+
+#[test]
+fn unexpected_packets_from_clients_are_dropped() {
+    let hub = VirtualHub::new();
+    let mut server_config = test_server_config(&hub);
+    server_config.connection_migration = true;
+    let server_addr = server_config.addr;
+    let mut server = UnetServer::from_config(server_config).unwrap();
+
+    // Handshake by hand, so we can send whatever we like afterwards
+    let client = hub.endpoint(test_client_addr(0)).unwrap();
+    let connection_request = Packet::ConnectionRequest(ConnectionRequest::new(UnetId(1)));
+    client
+        .send_to(&connection_request.as_bytes(), server_addr)
+        .unwrap();
+    server.tick();
+    let Packet::ChallengeRequest(challenge_request) = receive(&client) else {
+        panic!("Expected a ChallengeRequest");
+    };
+    let response = ChallengeResponse::new(UnetId(1), 7, challenge_request.cookie);
+    client
+        .send_to(&Packet::ChallengeResponse(response).as_bytes(), server_addr)
+        .unwrap();
+    server.tick();
+    let Packet::KeepAlive(keep_alive) = receive(&client) else {
+        panic!("Expected a KeepAlive");
+    };
+
+    let session_id = keep_alive.header.client_id;
+    for (sequence, mut packet) in unexpected_packets(session_id).into_iter().enumerate() {
+        packet.set_sequence(sequence as u64 + 1);
+        client.send_to(&packet.as_bytes(), server_addr).unwrap();
+    }
+    client.send_to(&[200; 40], server_addr).unwrap();
+    server.tick();
+
+    let connection_identifier = ConnectionIdentifier::new(session_id, test_client_addr(0));
+    let stats = server.stats(connection_identifier).unwrap();
+    assert_eq!(stats.packets_dropped, 4);
+    assert_eq!(server.connection_count(), 1);
+}
+
+#[test]
+fn unexpected_packets_from_the_server_are_dropped() {
+    let hub = VirtualHub::new();
+    let mut client_config = ClientConfig::new();
+    let server_addr = client_config.target;
+    client_config.transport = Some(Box::new(hub.endpoint(test_client_addr(0)).unwrap()));
+    let mut client = UnetClient::from_config(client_config).unwrap();
+
+    // Play the server by hand, so we can send whatever we like afterwards
+    let server = hub.endpoint(server_addr).unwrap();
+    client.tick();
+    let Packet::ConnectionRequest(_) = receive(&server) else {
+        panic!("Expected a ConnectionRequest");
+    };
+    let challenge_request = ChallengeRequest::new(client.id, Cookie::default());
+    server
+        .send_to(
+            &Packet::ChallengeRequest(challenge_request).as_bytes(),
+            test_client_addr(0),
+        )
+        .unwrap();
+    client.tick();
+    let Packet::ChallengeResponse(_) = receive(&server) else {
+        panic!("Expected a ChallengeResponse");
+    };
+    let session_id = UnetId(1);
+    server
+        .send_to(
+            &Packet::KeepAlive(KeepAlive::new(session_id)).as_bytes(),
+            test_client_addr(0),
+        )
+        .unwrap();
+    client.tick();
+    assert_eq!(client.state, ClientState::Connected);
+
+    let client_packets = [
+        Packet::ConnectionRequest(ConnectionRequest::new(session_id)),
+        Packet::ChallengeResponse(ChallengeResponse::new(session_id, 7, Cookie::default())),
+        Packet::PathResponse(PathResponse::new(session_id, 1, 1)),
+    ];
+    for (sequence, mut packet) in client_packets.into_iter().enumerate() {
+        packet.set_sequence(sequence as u64 + 1);
+        server
+            .send_to(&packet.as_bytes(), test_client_addr(0))
+            .unwrap();
+    }
+    server.send_to(&[200; 40], test_client_addr(0)).unwrap();
+    client.tick();
+
+    assert_eq!(client.stats().packets_dropped, 4);
+    assert_eq!(client.state, ClientState::Connected);
+}
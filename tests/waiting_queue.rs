@@ -0,0 +1,121 @@
+use std::time::Duration;
+use unet::client::{ClientState, UnetClient};
+use unet::config::test::{connect_clients, multi_client_test_config, tick_all};
+use unet::packet::disconnect::DisconnectReason;
+use unet::server::waiting_queue::WaitingQueueConfig;
+use unet::server::UnetServer;
+
+fn setup(clients: usize, max_length: usize) -> (UnetServer, Vec<UnetClient>) {
+    let (mut server_config, client_configs) = multi_client_test_config(clients);
+    server_config.max_connections = 1;
+    let mut waiting_queue = WaitingQueueConfig::new();
+    waiting_queue.max_length = max_length;
+    server_config.waiting_queue = Some(waiting_queue);
+
+    let server = UnetServer::from_config(server_config).unwrap();
+    (server, connect_clients(client_configs))
+}
+
+#[test]
+fn full_server_queues_clients() {
+    let (mut server, clients) = setup(3, 8);
+    let [mut first, mut second, mut third]: [UnetClient; 3] = clients.try_into().unwrap();
+
+    tick_all(&mut server, &mut [&mut first], 5);
+    assert_eq!(first.state, ClientState::Connected);
+
+    tick_all(&mut server, &mut [&mut first, &mut second], 5);
+    tick_all(&mut server, &mut [&mut first, &mut second, &mut third], 5);
+    assert_eq!(second.state, ClientState::Queued);
+    assert_eq!(second.queue_position(), Some(1));
+    assert_eq!(third.state, ClientState::Queued);
+    assert_eq!(third.queue_position(), Some(2));
+    assert_eq!(server.connection_count(), 1);
+    assert_eq!(server.waiting_count(), 2);
+
+    // Waiting doesn't count against the connect timeout
+    tick_all(&mut server, &mut [&mut first, &mut second, &mut third], 200);
+    assert_eq!(second.state, ClientState::Queued);
+    assert_eq!(third.state, ClientState::Queued);
+}
+
+#[test]
+fn queued_clients_get_freed_slots() {
+    let (mut server, clients) = setup(3, 8);
+    let [mut first, mut second, mut third]: [UnetClient; 3] = clients.try_into().unwrap();
+
+    tick_all(&mut server, &mut [&mut first], 5);
+    tick_all(&mut server, &mut [&mut first, &mut second], 5);
+    tick_all(&mut server, &mut [&mut first, &mut second, &mut third], 5);
+    assert_eq!(server.waiting_count(), 2);
+
+    // First client goes quiet and times out, the next one in line takes its slot
+    tick_all(&mut server, &mut [&mut second, &mut third], 100);
+    assert_eq!(second.state, ClientState::Connected);
+    assert_eq!(second.queue_position(), None);
+    assert!(second.session_id().is_some());
+    assert_eq!(third.state, ClientState::Queued);
+    assert_eq!(third.queue_position(), Some(1));
+    assert_eq!(server.connection_count(), 1);
+    assert_eq!(server.waiting_count(), 1);
+
+    server.set_max_connections(2, false);
+    tick_all(&mut server, &mut [&mut second, &mut third], 5);
+    assert_eq!(third.state, ClientState::Connected);
+    assert_eq!(server.connection_count(), 2);
+    assert_eq!(server.waiting_count(), 0);
+}
+
+#[test]
+fn full_queue_turns_clients_away() {
+    let (mut server, clients) = setup(3, 1);
+    let [mut first, mut second, mut third]: [UnetClient; 3] = clients.try_into().unwrap();
+
+    tick_all(&mut server, &mut [&mut first], 5);
+    tick_all(&mut server, &mut [&mut first, &mut second], 5);
+    tick_all(&mut server, &mut [&mut first, &mut second, &mut third], 5);
+    assert_eq!(second.state, ClientState::Queued);
+    assert_eq!(
+        third.state,
+        ClientState::Disconnected(DisconnectReason::ServerFull)
+    );
+}
+
+#[test]
+fn queue_times_out() {
+    let (mut server_config, client_configs) = multi_client_test_config(2);
+    server_config.max_connections = 1;
+    let mut waiting_queue = WaitingQueueConfig::new();
+    waiting_queue.timeout = Duration::from_secs(2);
+    server_config.waiting_queue = Some(waiting_queue);
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let [mut first, mut second]: [UnetClient; 2] =
+        connect_clients(client_configs).try_into().unwrap();
+
+    tick_all(&mut server, &mut [&mut first], 5);
+    tick_all(&mut server, &mut [&mut first, &mut second], 5);
+    assert_eq!(second.state, ClientState::Queued);
+
+    tick_all(&mut server, &mut [&mut first, &mut second], 50);
+    assert_eq!(
+        second.state,
+        ClientState::Disconnected(DisconnectReason::Timeout)
+    );
+    assert_eq!(server.waiting_count(), 0);
+}
+
+#[test]
+fn no_queue_by_default() {
+    let (mut server_config, client_configs) = multi_client_test_config(2);
+    server_config.max_connections = 1;
+    let mut server = UnetServer::from_config(server_config).unwrap();
+    let [mut first, mut second]: [UnetClient; 2] =
+        connect_clients(client_configs).try_into().unwrap();
+
+    tick_all(&mut server, &mut [&mut first], 5);
+    tick_all(&mut server, &mut [&mut first, &mut second], 5);
+    assert_eq!(
+        second.state,
+        ClientState::Disconnected(DisconnectReason::ServerFull)
+    );
+}